@@ -0,0 +1,59 @@
+//! Compare `parse_password` with the parser day 2 used to have, which split each line
+//! into `Vec`s.
+//!
+//! Run with `cargo bench --bench day02_parse`.
+
+use std::hint::black_box;
+
+use rs_aoc::password::{parse_password, Password};
+
+/// How many times to parse the whole input for each measurement
+const RUNS: u32 = 100;
+
+/// The old parser, which collects `split_whitespace()` and `split('-')` into `Vec`s and
+/// panics on a bad line
+fn split_password(line: &str) -> Password<'_> {
+    let parts: Vec<&str> = line.split_whitespace().collect();
+    let lower_upper: Vec<&str> = parts[0].split('-').collect();
+    let lower: usize = lower_upper[0].parse().expect("Could not parse lower bound");
+    let upper: usize = lower_upper[1].parse().expect("Could not parse upper bound");
+    let letter = parts[1]
+        .strip_suffix(':')
+        .filter(|letter| !letter.is_empty())
+        .expect("Could not get that required letter");
+    Password {
+        lower,
+        upper,
+        letter,
+        password: parts[2],
+    }
+}
+
+/// Parse every line of `input` `RUNS` times with `parse`, and print the average time
+/// per line
+fn time<'a, T>(label: &str, input: &'a str, parse: impl Fn(&'a str) -> T) {
+    let lines = input.lines().count();
+    let start = std::time::Instant::now();
+    for _ in 0..RUNS {
+        for line in input.lines() {
+            black_box(parse(black_box(line)));
+        }
+    }
+    println!(
+        "{} took {:.1} nanoseconds per line",
+        label,
+        start.elapsed().as_nanos() as f64 / (RUNS as usize * lines) as f64
+    );
+}
+
+fn main() {
+    let input = std::fs::read_to_string("inputs/day02.txt").expect("Could not read day 2 input");
+    let input = input.as_str();
+
+    for line in input.lines() {
+        assert_eq!(parse_password(line).as_ref(), Ok(&split_password(line)));
+    }
+
+    time("Splitting into Vecs", input, split_password);
+    time("parse_password", input, |line| parse_password(line).ok());
+}
@@ -0,0 +1,86 @@
+//! Compare `TreeField` with the `Vec<Vec<bool>>` that day 3 used to keep its trees in.
+//!
+//! Run with `cargo bench --bench tree_field`.
+
+use std::hint::black_box;
+
+use rs_aoc::grid::TreeField;
+
+/// How many times to repeat each measurement
+const RUNS: u32 = 1000;
+
+/// The slopes from day 3's second part, as (right, down)
+const SLOPES: [(usize, usize); 5] = [(1, 1), (3, 1), (5, 1), (7, 1), (1, 2)];
+
+fn create_vec_field(input: &str) -> Vec<Vec<bool>> {
+    input
+        .lines()
+        .map(|row| row.chars().map(|c| c == '#').collect())
+        .collect()
+}
+
+/// Count the trees hit on every slope, looking cells up with `is_tree(row, col)`, where
+/// `col` has already been wrapped around
+fn count_all_slopes(height: usize, width: usize, is_tree: impl Fn(usize, usize) -> bool) -> usize {
+    SLOPES
+        .iter()
+        .map(|&(right, down)| {
+            (0..height)
+                .step_by(down)
+                .enumerate()
+                .filter(|&(step, row)| is_tree(row, (step * right) % width))
+                .count()
+        })
+        .sum()
+}
+
+/// Time `f` over `RUNS` runs, and print the average
+fn time<T>(label: &str, f: impl Fn() -> T) {
+    let start = std::time::Instant::now();
+    for _ in 0..RUNS {
+        black_box(f());
+    }
+    println!(
+        "{} took {:.3} microseconds",
+        label,
+        start.elapsed().as_secs_f64() * 1e6 / RUNS as f64
+    );
+}
+
+fn main() {
+    let input = std::fs::read_to_string("inputs/day03.txt").expect("Could not read day 3 input");
+    let input = input.as_str();
+
+    time("Building Vec<Vec<bool>>", || {
+        create_vec_field(black_box(input))
+    });
+    time("Building TreeField", || TreeField::parse(black_box(input)));
+
+    let vec_field = create_vec_field(input);
+    let tree_field = TreeField::parse(input);
+    let (height, width) = (tree_field.height(), tree_field.width());
+    assert_eq!(
+        count_all_slopes(height, width, |row, col| vec_field[row][col]),
+        count_all_slopes(height, width, |row, col| tree_field.get_wrapping(row, col)),
+    );
+
+    time("Walking slopes in Vec<Vec<bool>>", || {
+        count_all_slopes(height, width, |row, col| black_box(&vec_field)[row][col])
+    });
+    time("Walking slopes in TreeField", || {
+        count_all_slopes(height, width, |row, col| {
+            black_box(&tree_field).get_wrapping(row, col)
+        })
+    });
+
+    println!();
+    println!(
+        "Vec<Vec<bool>> uses {} bytes of cells in {} allocations",
+        vec_field.iter().map(Vec::capacity).sum::<usize>(),
+        vec_field.len() + 1
+    );
+    println!(
+        "TreeField uses {} bytes of cells in 1 allocation",
+        height * width.div_ceil(64) * 8
+    );
+}
@@ -1,14 +1,260 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
 use itertools::Itertools;
+use num_bigint::BigUint;
+use num_traits::{CheckedMul, One};
+
+/// Entries from the expense report that sum to the target
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct KSumMatch {
+    /// Where the entries are in the input, in ascending order
+    indices: Vec<usize>,
+    /// The entries themselves, in the same order as `indices`
+    entries: Vec<usize>,
+}
+
+impl KSumMatch {
+    fn new(inputs: &[usize], mut indices: Vec<usize>) -> Self {
+        indices.sort_unstable();
+        let entries = indices.iter().map(|&i| inputs[i]).collect();
+        KSumMatch { indices, entries }
+    }
+
+    /// Multiply the entries together as a `T`, such as `usize`, `u128` or `BigUint`,
+    /// failing rather than overflowing if the product does not fit
+    fn product<T>(&self) -> Result<T, ProductOverflow>
+    where
+        T: CheckedMul + One + TryFrom<usize>,
+    {
+        self.entries
+            .iter()
+            .try_fold(T::one(), |acc, &entry| {
+                acc.checked_mul(&T::try_from(entry).ok()?)
+            })
+            .ok_or_else(|| ProductOverflow {
+                entries: self.entries.clone(),
+                type_name: std::any::type_name::<T>(),
+            })
+    }
+}
+
+/// The product of some entries is too big for the type it was asked for in
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ProductOverflow {
+    entries: Vec<usize>,
+    type_name: &'static str,
+}
+
+impl std::fmt::Display for ProductOverflow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "the product of {:?} does not fit in a {}",
+            self.entries, self.type_name
+        )
+    }
+}
+
+impl std::error::Error for ProductOverflow {}
+
+/// Find `k` distinct entries of `inputs` that sum to `target`.
+///
+/// For k = 2 and k = 3 this sorts the entries and closes in on the target with two
+/// pointers, in O(n log n) and O(n^2). For larger k it meets in the middle, pairing up
+/// the sums of the first half of each combination with those of the second half, in
+/// roughly O(n^(k/2)) time and memory.
+fn find_k_sum(inputs: &[usize], k: usize, target: usize) -> Option<KSumMatch> {
+    let indices = match k {
+        0 => (target == 0).then(Vec::new),
+        1 => inputs.iter().position(|&x| x == target).map(|i| vec![i]),
+        2 | 3 => {
+            // Sort the entries, but keep track of where they came from
+            let sorted: Vec<(usize, usize)> =
+                inputs.iter().copied().zip(0..).sorted_unstable().collect();
+            if k == 2 {
+                two_sum(&sorted, target)
+            } else {
+                three_sum(&sorted, target)
+            }
+        }
+        _ => meet_in_the_middle(inputs, k, target),
+    }?;
+    Some(KSumMatch::new(inputs, indices))
+}
+
+/// Find two entries of `sorted` that sum to `target`, returning their original indices.
+/// `sorted` holds (entry, original index) pairs sorted by entry.
+fn two_sum(sorted: &[(usize, usize)], target: usize) -> Option<Vec<usize>> {
+    if sorted.len() < 2 {
+        return None;
+    }
+    let mut lo = 0;
+    let mut hi = sorted.len() - 1;
+    while lo < hi {
+        // A sum too big to represent is certainly too big for the target
+        let sum = sorted[lo].0.checked_add(sorted[hi].0);
+        match sum.map_or(Ordering::Greater, |sum| sum.cmp(&target)) {
+            Ordering::Less => lo += 1,
+            Ordering::Greater => hi -= 1,
+            Ordering::Equal => return Some(vec![sorted[lo].1, sorted[hi].1]),
+        }
+    }
+    None
+}
 
-fn solve(inputs: &[usize], n_entries: usize) -> usize {
-    // Go through all length `n_entries` combinations of inputs, if the sum is 2020,
-    // return the product of those numbers.
-    for combo in inputs.iter().combinations(n_entries) {
-        if combo.iter().copied().sum::<usize>() == 2020 {
-            return combo.iter().copied().product();
+/// Find three entries of `sorted` that sum to `target`, returning their original indices
+fn three_sum(sorted: &[(usize, usize)], target: usize) -> Option<Vec<usize>> {
+    for (i, &(entry, idx)) in sorted.iter().enumerate() {
+        // Everything after this is at least as big, so nothing can fit any more
+        if entry > target {
+            break;
+        }
+        if let Some(mut rest) = two_sum(&sorted[i + 1..], target - entry) {
+            rest.push(idx);
+            return Some(rest);
         }
     }
-    panic!("Did not find numbers summing to 2020")
+    None
+}
+
+/// Find `k` entries that sum to `target` by splitting each combination of indices into
+/// its lowest `k / 2` indices and the rest.
+fn meet_in_the_middle(inputs: &[usize], k: usize, target: usize) -> Option<Vec<usize>> {
+    let first_len = k / 2;
+
+    // Map each sum of a first half to the combinations that make it
+    let mut first_halves: HashMap<usize, Vec<Vec<usize>>> = HashMap::new();
+    for combo in (0..inputs.len()).combinations(first_len) {
+        if let Some(sum) = checked_sum(inputs, &combo).filter(|&sum| sum <= target) {
+            first_halves.entry(sum).or_default().push(combo);
+        }
+    }
+
+    // For each second half, look for a first half that makes up the rest of the target,
+    // and that only uses indices below the second half's
+    for combo in (0..inputs.len()).combinations(k - first_len) {
+        let Some(candidates) = checked_sum(inputs, &combo)
+            .and_then(|sum| target.checked_sub(sum))
+            .and_then(|r| first_halves.get(&r))
+        else {
+            continue;
+        };
+        if let Some(first) = candidates
+            .iter()
+            .find(|first| first.last().is_none_or(|&last| last < combo[0]))
+        {
+            return Some(first.iter().chain(combo.iter()).copied().collect());
+        }
+    }
+    None
+}
+
+/// Iterates over every combination of `k` entries that sums to a target.
+///
+/// Entries are never reused, but equal entries at different indices are different
+/// entries, so each of them gives its own combination.
+struct KSums<'a> {
+    inputs: &'a [usize],
+    /// (entry, original index) pairs, sorted by entry
+    sorted: Vec<(usize, usize)>,
+    k: usize,
+    target: usize,
+    /// Positions in `sorted` of the entries in the combination being built
+    stack: Vec<usize>,
+    /// The sum of the entries in `stack`
+    sum: usize,
+    /// The position in `sorted` to try adding to the combination next
+    next: usize,
+    done: bool,
+}
+
+impl<'a> KSums<'a> {
+    fn new(inputs: &'a [usize], k: usize, target: usize) -> Self {
+        let sorted = inputs.iter().copied().zip(0..).sorted_unstable().collect();
+        KSums {
+            inputs,
+            sorted,
+            k,
+            target,
+            stack: Vec::with_capacity(k),
+            sum: 0,
+            next: 0,
+            done: false,
+        }
+    }
+
+    /// Take the last entry off the combination, and move on to the ones after it.
+    /// Returns false once there is nothing left to take off.
+    fn backtrack(&mut self) -> bool {
+        match self.stack.pop() {
+            Some(pos) => {
+                self.sum -= self.sorted[pos].0;
+                self.next = pos + 1;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl Iterator for KSums<'_> {
+    type Item = KSumMatch;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            if self.stack.len() == self.k {
+                let found = (self.sum == self.target).then(|| {
+                    let indices = self.stack.iter().map(|&pos| self.sorted[pos].1).collect();
+                    KSumMatch::new(self.inputs, indices)
+                });
+                self.done = !self.backtrack();
+                if found.is_some() {
+                    return found;
+                }
+                continue;
+            }
+
+            // The entries are sorted, so if filling the rest of the combination with the
+            // next entry already overshoots, so will every later entry
+            let remaining = self.k - self.stack.len();
+            let fits = self.next + remaining <= self.sorted.len()
+                && self.sorted[self.next]
+                    .0
+                    .checked_mul(remaining)
+                    .and_then(|rest| rest.checked_add(self.sum))
+                    .is_some_and(|lowest| lowest <= self.target);
+            if fits {
+                self.stack.push(self.next);
+                self.sum += self.sorted[self.next].0;
+                self.next += 1;
+            } else {
+                self.done = !self.backtrack();
+            }
+        }
+        None
+    }
+}
+
+/// Every combination of `k` distinct entries of `inputs` that sums to `target`
+fn all_k_sums(inputs: &[usize], k: usize, target: usize) -> KSums<'_> {
+    KSums::new(inputs, k, target)
+}
+
+/// The sum of the entries at `indices`, or `None` if it overflows
+fn checked_sum(inputs: &[usize], indices: &[usize]) -> Option<usize> {
+    indices
+        .iter()
+        .try_fold(0_usize, |acc, &i| acc.checked_add(inputs[i]))
+}
+
+/// The product of the first `n_entries` entries found summing to `target` as a `T`, or
+/// `None` if there aren't any
+fn solve<T>(inputs: &[usize], n_entries: usize, target: usize) -> Option<Result<T, ProductOverflow>>
+where
+    T: CheckedMul + One + TryFrom<usize>,
+{
+    find_k_sum(inputs, n_entries, target).map(|m| m.product())
 }
 
 fn main() {
@@ -27,7 +273,9 @@ fn main() {
 
     let part1_time = std::time::Instant::now();
     // Solve for the first puzzle
-    let part1_soln = solve(&input_ints, 2);
+    let part1_soln: usize = solve(&input_ints, 2, 2020)
+        .expect("Did not find two numbers summing to 2020")
+        .expect("Could not multiply the two numbers");
     println!(
         "Part 1 took {:.6} microseconds",
         part1_time.elapsed().as_micros()
@@ -35,7 +283,9 @@ fn main() {
 
     let part2_time = std::time::Instant::now();
     // Solve for the second puzzle
-    let part2_soln = solve(&input_ints, 3);
+    let part2_soln: usize = solve(&input_ints, 3, 2020)
+        .expect("Did not find three numbers summing to 2020")
+        .expect("Could not multiply the three numbers");
     println!(
         "Part 2 took {:.6} microseconds",
         part2_time.elapsed().as_micros()
@@ -44,18 +294,175 @@ fn main() {
     println!();
     println!("First puzzle: {}", part1_soln);
     println!("Second puzzle: {}", part2_soln);
+    println!(
+        "Combinations summing to 2020: {} pairs, {} triples",
+        all_k_sums(&input_ints, 2, 2020).count(),
+        all_k_sums(&input_ints, 3, 2020).count()
+    );
+
+    // With `--entries <k>`, multiply k entries summing to 2020, which can be too big for
+    // any fixed size integer
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(idx) = args.iter().position(|a| a == "--entries") {
+        let k: usize = args
+            .get(idx + 1)
+            .and_then(|k| k.parse().ok())
+            .expect("--entries must be followed by a number");
+        match solve::<BigUint>(&input_ints, k, 2020) {
+            Some(product) => println!(
+                "Product of {} entries: {}",
+                k,
+                product.expect("A BigUint can't overflow")
+            ),
+            None => println!("No {} entries sum to 2020", k),
+        }
+    }
 }
 
 #[test]
 fn part1() {
     // Test that in the vec [1721, 979, 366, 299, 675, 1456], the sum of the two numbers
     // that sum to 2020 is 514579
-    assert_eq!(solve(&[1721, 979, 366, 299, 675, 1456], 2), 514579);
+    assert_eq!(
+        solve::<usize>(&[1721, 979, 366, 299, 675, 1456], 2, 2020),
+        Some(Ok(514579))
+    );
 }
 
 #[test]
 fn part2() {
     // Test that in the vec [1721, 979, 366, 299, 675, 1456], the sum of the three numbers
     // that sum to 2020 is 241861950
-    assert_eq!(solve(&[1721, 979, 366, 299, 675, 1456], 3), 241861950);
+    assert_eq!(
+        solve::<usize>(&[1721, 979, 366, 299, 675, 1456], 3, 2020),
+        Some(Ok(241861950))
+    );
+}
+
+#[test]
+fn test_find_k_sum_indices() {
+    let inputs = [1721, 979, 366, 299, 675, 1456];
+    let got = find_k_sum(&inputs, 2, 2020).expect("Could not find two entries");
+    assert_eq!(got.indices, vec![0, 3]);
+    assert_eq!(got.entries, vec![1721, 299]);
+
+    let got = find_k_sum(&inputs, 3, 2020).expect("Could not find three entries");
+    assert_eq!(got.indices, vec![1, 2, 4]);
+    assert_eq!(got.entries, vec![979, 366, 675]);
+
+    // 979 + 366 + 299 + 675 = 2319
+    let got = find_k_sum(&inputs, 4, 2319).expect("Could not find four entries");
+    assert_eq!(got.indices, vec![1, 2, 3, 4]);
+
+    // An entry can't be used twice
+    assert_eq!(find_k_sum(&[1010, 5], 2, 2020), None);
+    assert!(find_k_sum(&[1010, 1010], 2, 2020).is_some());
+}
+
+#[test]
+fn test_solve_missing() {
+    assert_eq!(solve::<usize>(&[1721, 979, 366], 2, 2020), None);
+    assert_eq!(solve::<usize>(&[], 2, 2020), None);
+}
+
+#[test]
+fn test_product_overflow() {
+    // 2^40 * 2^40 doesn't fit in a u64, but does in a u128
+    let big = 1_usize << 40;
+    let got = find_k_sum(&[big, 1, big], 2, 2 * big).expect("Could not find two entries");
+    assert_eq!(
+        got.product::<u64>(),
+        Err(ProductOverflow {
+            entries: vec![big, big],
+            type_name: "u64",
+        })
+    );
+    assert_eq!(got.product::<u128>(), Ok(1_u128 << 80));
+
+    // 2^160 is too big even for a u128
+    let got = find_k_sum(&[big; 4], 4, 4 * big).expect("Could not find four entries");
+    assert!(got.product::<u128>().is_err());
+    assert_eq!(got.product::<BigUint>(), Ok(BigUint::one() << 160_u32));
+
+    // The caller picks the type through solve too
+    let inputs = [big, 1, big];
+    assert!(solve::<u64>(&inputs, 2, 2 * big).is_some_and(|p| p.is_err()));
+    assert_eq!(solve::<u128>(&inputs, 2, 2 * big), Some(Ok(1_u128 << 80)));
+}
+
+#[test]
+fn test_sum_overflow() {
+    // Sums past usize::MAX must not wrap around to the target
+    let inputs = [usize::MAX, 2, usize::MAX, 1];
+    assert_eq!(find_k_sum(&inputs, 2, 0), None);
+    assert_eq!(
+        find_k_sum(&inputs, 2, 3).map(|m| m.indices),
+        Some(vec![1, 3])
+    );
+    assert_eq!(all_k_sums(&inputs, 2, 0).count(), 0);
+    assert_eq!(find_k_sum(&inputs, 4, 2), None);
+}
+
+#[test]
+fn test_all_k_sums() {
+    let inputs = [1721, 979, 366, 299, 675, 1456];
+    let got: Vec<KSumMatch> = all_k_sums(&inputs, 2, 2020).collect();
+    assert_eq!(got, vec![KSumMatch::new(&inputs, vec![0, 3])]);
+
+    // 5 + 15 and 8 + 12
+    let inputs = [5, 8, 12, 15];
+    let got: Vec<Vec<usize>> = all_k_sums(&inputs, 2, 20).map(|m| m.entries).collect();
+    assert_eq!(got, vec![vec![5, 15], vec![8, 12]]);
+
+    assert_eq!(all_k_sums(&inputs, 3, 1000).next(), None);
+}
+
+#[test]
+fn test_all_k_sums_duplicates() {
+    // Any two of the three 1010s, but never one of them twice
+    let inputs = [1010, 1010, 1010];
+    let got: Vec<Vec<usize>> = all_k_sums(&inputs, 2, 2020).map(|m| m.indices).collect();
+    assert_eq!(got.len(), 3);
+    for indices in [vec![0, 1], vec![0, 2], vec![1, 2]] {
+        assert!(got.contains(&indices));
+    }
+    assert_eq!(all_k_sums(&[1010], 2, 2020).count(), 0);
+}
+
+proptest::proptest! {
+    #[test]
+    fn test_find_k_sum_matches_brute_force(
+        inputs in proptest::collection::vec(0_usize..=20, 0..=9),
+        k in 0_usize..=6,
+        target in 0_usize..=60,
+    ) {
+        let expected = inputs
+            .iter()
+            .combinations(k)
+            .any(|combo| combo.into_iter().sum::<usize>() == target);
+        let got = find_k_sum(&inputs, k, target);
+        proptest::prop_assert_eq!(got.is_some(), expected);
+        if let Some(got) = got {
+            proptest::prop_assert_eq!(got.indices.len(), k);
+            proptest::prop_assert!(got.indices.windows(2).all(|w| w[0] < w[1]));
+            proptest::prop_assert_eq!(got.entries.iter().sum::<usize>(), target);
+        }
+    }
+
+    #[test]
+    fn test_all_k_sums_matches_brute_force(
+        inputs in proptest::collection::vec(0_usize..=20, 0..=9),
+        k in 0_usize..=5,
+        target in 0_usize..=50,
+    ) {
+        let expected: Vec<Vec<usize>> = (0..inputs.len())
+            .combinations(k)
+            .filter(|combo| combo.iter().map(|&i| inputs[i]).sum::<usize>() == target)
+            .collect();
+        let got: Vec<Vec<usize>> = all_k_sums(&inputs, k, target)
+            .map(|m| m.indices)
+            .sorted()
+            .collect();
+        proptest::prop_assert_eq!(got, expected);
+    }
 }
@@ -1,116 +1,672 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use rs_aoc::password::{parse_password, Password};
+use unicode_segmentation::UnicodeSegmentation;
+
 fn part1(inputs: &[Password]) -> usize {
-    // Iterate over the inputs, filter out bad ones, and count those that get through
-    inputs.iter().filter(|line| part1_pw_is_valid(line)).count()
+    count_valid(inputs, &SledRental::default())
 }
 
 fn part2(inputs: &[Password]) -> usize {
+    count_valid(inputs, &Toboggan::default())
+}
+
+fn count_valid(inputs: &[Password], policy: &dyn Policy) -> usize {
     // Iterate over the inputs, filter out bad ones, and count those that get through
-    inputs.iter().filter(|line| part2_pw_is_valid(line)).count()
+    inputs.iter().filter(|line| policy.is_valid(line)).count()
 }
 
-#[derive(Debug)]
-struct Password<'a> {
-    lower: usize,
-    upper: usize,
-    letter: char,
-    password: &'a str,
+#[cfg(test)]
+fn new_password(line: &str) -> Password<'_> {
+    parse_password(line).unwrap_or_else(|e| panic!("Could not parse {:?}: {}", line, e))
 }
 
-fn new_password(line: &str) -> Password {
-    // Examples:
-    // 1-3 a: abcde
-    // 1-3 b: cdefg
-    // 2-9 c: ccccccccc
+/// What a policy counts as one letter of a password
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum TextUnits {
+    /// Unicode code points, i.e. Rust `char`s
+    #[default]
+    CodePoints,
+    /// Extended grapheme clusters, which is what a reader would call a character
+    Graphemes,
+}
 
-    // Split on whitespace
-    let parts: Vec<&str> = line.split_whitespace().collect();
+impl TextUnits {
+    fn split<'s>(self, text: &'s str) -> Box<dyn Iterator<Item = &'s str> + 's> {
+        match self {
+            TextUnits::CodePoints => Box::new(
+                text.char_indices()
+                    .map(move |(i, c)| &text[i..i + c.len_utf8()]),
+            ),
+            TextUnits::Graphemes => Box::new(text.graphemes(true)),
+        }
+    }
 
-    // The first part is the range
-    let lower_upper: Vec<&str> = parts[0].split('-').collect();
-    let lower: usize = lower_upper[0].parse().expect("Could not parse lower bound");
-    let upper: usize = lower_upper[1].parse().expect("Could not parse upper bound");
+    /// The name used in policy configs
+    fn name(self) -> &'static str {
+        match self {
+            TextUnits::CodePoints => "code_points",
+            TextUnits::Graphemes => "graphemes",
+        }
+    }
 
-    // The second part is the letter that must exist in the password
-    let letter = parts[1]
-        .chars()
-        .next()
-        .expect("Could not get that required letter");
+    fn from_name(name: &str) -> Option<Self> {
+        [TextUnits::CodePoints, TextUnits::Graphemes]
+            .into_iter()
+            .find(|units| units.name() == name)
+    }
 
-    // The third part is the password
-    let password = parts[2];
+    /// What the units are called, for explaining violations
+    fn plural(self) -> &'static str {
+        match self {
+            TextUnits::CodePoints => "code points",
+            TextUnits::Graphemes => "grapheme clusters",
+        }
+    }
 
-    Password {
-        lower,
-        upper,
-        letter,
-        password,
+    /// Check that the password's letter is exactly one of these units. A letter like
+    /// `ab`, or `e` with a combining accent when counting code points, can never match
+    /// one letter of the password, so it would fail for a misleading reason. The policy
+    /// is only described if the letter is wrong.
+    fn check_letter(self, policy: &dyn Policy, pw: &Password) -> Result<(), Violation> {
+        let units = self.split(pw.letter).count();
+        if units == 1 {
+            return Ok(());
+        }
+        Err(Violation {
+            policy: policy.describe(),
+            observed: Observed::LetterUnits(units),
+            reason: format!(
+                "the letter {} is {} {}, not one",
+                pw.letter,
+                units,
+                self.plural()
+            ),
+        })
+    }
+
+    /// Describe a policy called `policy` that uses these units
+    fn describe(self, policy: &str) -> String {
+        match self {
+            TextUnits::CodePoints => policy.to_string(),
+            units => format!("{}({})", policy, units.name()),
+        }
     }
 }
 
-fn part1_pw_is_valid(pw: &Password) -> bool {
-    // The password policy indicates the lowest and highest number of times a given
-    // letter must appear for the password to be valid. For example, 1-3 a means that
-    // the password must contain a at least 1 time and at most 3 times.
-    // Examples:
-    // 1-3 a: abcde
-    // 1-3 b: cdefg
-    // 2-9 c: ccccccccc
+/// What a policy looked at in a password when deciding it was invalid
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Observed {
+    /// How many units the letter is made of, when it isn't just one
+    LetterUnits(usize),
+    /// How many times the letter appears
+    Count(usize),
+    /// What is at each of the two positions, or `None` if there is no such position
+    Positions {
+        first: Option<String>,
+        second: Option<String>,
+    },
+    /// How many of the combined policies the password passed
+    Passed { passed: usize, of: usize },
+}
+
+impl std::fmt::Display for Observed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let or_none = |letter: &Option<String>| letter.as_deref().unwrap_or("none").to_string();
+        match self {
+            Observed::LetterUnits(units) => write!(f, "letter of {} units", units),
+            Observed::Count(count) => write!(f, "count {}", count),
+            Observed::Positions { first, second } => {
+                write!(f, "positions {}, {}", or_none(first), or_none(second))
+            }
+            Observed::Passed { passed, of } => write!(f, "passed {} of {}", passed, of),
+        }
+    }
+}
+
+/// Why a password is invalid under a policy
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Violation {
+    /// The description of the policy that was violated
+    policy: String,
+    observed: Observed,
+    reason: String,
+}
+
+/// A rule for deciding whether a password is valid, given the numbers and letter it was
+/// listed with
+trait Policy {
+    /// Describe the policy, in the syntax of a policy config
+    fn describe(&self) -> String;
+
+    /// Check the password, explaining why it isn't valid if it isn't
+    fn check(&self, pw: &Password) -> Result<(), Violation>;
+
+    fn is_valid(&self, pw: &Password) -> bool {
+        self.check(pw).is_ok()
+    }
+}
+
+/// The sled rental place's interpretation of the policy
+#[derive(Debug, Clone, Copy, Default)]
+struct SledRental {
+    units: TextUnits,
+}
+
+impl Policy for SledRental {
+    fn describe(&self) -> String {
+        self.units.describe("sled")
+    }
+
+    fn check(&self, pw: &Password) -> Result<(), Violation> {
+        // The password policy indicates the lowest and highest number of times a given
+        // letter must appear for the password to be valid. For example, 1-3 a means that
+        // the password must contain a at least 1 time and at most 3 times.
+        // Examples:
+        // 1-3 a: abcde
+        // 1-3 b: cdefg
+        // 2-9 c: ccccccccc
+
+        self.units.check_letter(self, pw)?;
+
+        // Count how many times the letter exists in the password
+        let letter_count = self
+            .units
+            .split(pw.password)
+            .filter(|&c| c == pw.letter)
+            .count();
+
+        let reason = if letter_count < pw.lower {
+            format!("{} appears fewer than {} times", pw.letter, pw.lower)
+        } else if letter_count > pw.upper {
+            format!("{} appears more than {} times", pw.letter, pw.upper)
+        } else {
+            return Ok(());
+        };
+        Err(Violation {
+            policy: self.describe(),
+            observed: Observed::Count(letter_count),
+            reason,
+        })
+    }
+}
+
+/// The Official Toboggan Corporate interpretation of the policy
+#[derive(Debug, Clone, Copy, Default)]
+struct Toboggan {
+    units: TextUnits,
+}
+
+impl Policy for Toboggan {
+    fn describe(&self) -> String {
+        self.units.describe("toboggan")
+    }
+
+    fn check(&self, pw: &Password) -> Result<(), Violation> {
+        // Each policy actually describes two positions in the password, where 1 means the
+        // first character, 2 means the second character, and so on. (Be careful; Toboggan
+        // Corporate Policies have no concept of "index zero"!) Exactly one of these
+        // positions must contain the given letter. Other occurrences of the letter are
+        // irrelevant for the purposes of policy enforcement.
+
+        // Given the same example list from above:
+
+        // 1-3 a: abcde is valid: position 1 contains a and position 3 does not.
+        // 1-3 b: cdefg is invalid: neither position 1 nor position 3 contains b.
+        // 2-9 c: ccccccccc is invalid: both position 2 and position 9 contain c.
+
+        // How many passwords are valid according to the new interpretation of the policies?
+        self.units.check_letter(self, pw)?;
+
+        // Positions count letters, not bytes, so split the password up first
+        let letters: Vec<&str> = self.units.split(pw.password).collect();
+        let at = |position: usize| {
+            position
+                .checked_sub(1)
+                .and_then(|idx| letters.get(idx))
+                .copied()
+        };
+        let first = at(pw.lower);
+        let second = at(pw.upper);
+
+        // Check that both positions actually exist. There is no position 0.
+        let reason = if first.is_none() || second.is_none() {
+            format!(
+                "positions {} and {} are not both within the {} letters of the password",
+                pw.lower,
+                pw.upper,
+                letters.len()
+            )
+        } else {
+            let char_in_first_pos = first == Some(pw.letter);
+            let char_in_second_pos = second == Some(pw.letter);
+
+            match (char_in_first_pos as usize) + (char_in_second_pos as usize) {
+                1 => return Ok(()),
+                0 => format!("neither position holds {}", pw.letter),
+                _ => format!("both positions hold {}", pw.letter),
+            }
+        };
+        Err(Violation {
+            policy: self.describe(),
+            observed: Observed::Positions {
+                first: first.map(str::to_string),
+                second: second.map(str::to_string),
+            },
+            reason,
+        })
+    }
+}
+
+/// Valid when every one of the policies is
+struct All(Vec<Rc<dyn Policy>>);
+
+impl Policy for All {
+    fn describe(&self) -> String {
+        format!("all({})", describe_all(&self.0))
+    }
+
+    fn check(&self, pw: &Password) -> Result<(), Violation> {
+        let failures = failures(&self.0, pw);
+        if failures.is_empty() {
+            return Ok(());
+        }
+        Err(Violation {
+            policy: self.describe(),
+            observed: Observed::Passed {
+                passed: self.0.len() - failures.len(),
+                of: self.0.len(),
+            },
+            reason: explain_all(&failures),
+        })
+    }
+}
+
+/// Valid when at least one of the policies is
+struct Any(Vec<Rc<dyn Policy>>);
+
+impl Policy for Any {
+    fn describe(&self) -> String {
+        format!("any({})", describe_all(&self.0))
+    }
+
+    fn check(&self, pw: &Password) -> Result<(), Violation> {
+        let failures = failures(&self.0, pw);
+        if failures.len() < self.0.len() {
+            return Ok(());
+        }
+        Err(Violation {
+            policy: self.describe(),
+            observed: Observed::Passed {
+                passed: 0,
+                of: self.0.len(),
+            },
+            reason: explain_all(&failures),
+        })
+    }
+}
+
+/// Valid when exactly `n` of the policies are
+struct Exactly {
+    n: usize,
+    policies: Vec<Rc<dyn Policy>>,
+}
+
+impl Policy for Exactly {
+    fn describe(&self) -> String {
+        format!("exactly({}, {})", self.n, describe_all(&self.policies))
+    }
+
+    fn check(&self, pw: &Password) -> Result<(), Violation> {
+        let passed = self.policies.iter().filter(|p| p.is_valid(pw)).count();
+        if passed == self.n {
+            return Ok(());
+        }
+        Err(Violation {
+            policy: self.describe(),
+            observed: Observed::Passed {
+                passed,
+                of: self.policies.len(),
+            },
+            reason: format!("expected exactly {} to pass", self.n),
+        })
+    }
+}
+
+fn describe_all(policies: &[Rc<dyn Policy>]) -> String {
+    policies
+        .iter()
+        .map(|p| p.describe())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// The violations of each of the policies that the password fails
+fn failures(policies: &[Rc<dyn Policy>], pw: &Password) -> Vec<Violation> {
+    policies.iter().filter_map(|p| p.check(pw).err()).collect()
+}
+
+fn explain_all(failures: &[Violation]) -> String {
+    failures
+        .iter()
+        .map(|v| format!("{} failed: {}", v.policy, v.reason))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// A password that failed an audit, and why
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Rejection<'a> {
+    /// The line of the input the password is on, starting from 1
+    line: usize,
+    password: &'a Password<'a>,
+    violation: Violation,
+}
+
+/// Every password that fails a policy
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct AuditReport<'a> {
+    rejections: Vec<Rejection<'a>>,
+}
+
+/// Check every password against `policy`. `inputs` should hold one password per line
+/// of the input, so that line numbers in the report are right.
+fn audit<'a>(inputs: &'a [Password<'a>], policy: &dyn Policy) -> AuditReport<'a> {
+    let rejections = inputs
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, password)| {
+            policy.check(password).err().map(|violation| Rejection {
+                line: idx + 1,
+                password,
+                violation,
+            })
+        })
+        .collect();
+    AuditReport { rejections }
+}
+
+impl AuditReport<'_> {
+    /// A JSON array with one object per rejected password
+    fn to_json(&self) -> String {
+        let rejections: Vec<serde_json::Value> = self
+            .rejections
+            .iter()
+            .map(|r| {
+                let observed = match &r.violation.observed {
+                    Observed::LetterUnits(units) => serde_json::json!({ "letter_units": units }),
+                    Observed::Count(count) => serde_json::json!({ "count": count }),
+                    Observed::Positions { first, second } => serde_json::json!({
+                        "positions": [
+                            { "position": r.password.lower, "letter": first },
+                            { "position": r.password.upper, "letter": second },
+                        ]
+                    }),
+                    Observed::Passed { passed, of } => {
+                        serde_json::json!({ "passed": passed, "of": of })
+                    }
+                };
+                serde_json::json!({
+                    "line": r.line,
+                    "password": r.password.password,
+                    "letter": r.password.letter,
+                    "lower": r.password.lower,
+                    "upper": r.password.upper,
+                    "policy": r.violation.policy,
+                    "observed": observed,
+                    "reason": r.violation.reason,
+                })
+            })
+            .collect();
+        serde_json::to_string_pretty(&rejections).expect("Could not serialize the report")
+    }
+
+    /// A CSV table with a header row, and one row per rejected password
+    fn to_csv(&self) -> String {
+        let mut csv = String::from("line,password,letter,lower,upper,policy,observed,reason\n");
+        for r in &self.rejections {
+            let fields = [
+                r.line.to_string(),
+                r.password.password.to_string(),
+                r.password.letter.to_string(),
+                r.password.lower.to_string(),
+                r.password.upper.to_string(),
+                r.violation.policy.clone(),
+                r.violation.observed.to_string(),
+                r.violation.reason.clone(),
+            ];
+            csv.push_str(
+                &fields
+                    .iter()
+                    .map(|f| csv_field(f))
+                    .collect::<Vec<_>>()
+                    .join(","),
+            );
+            csv.push('\n');
+        }
+        csv
+    }
+}
+
+/// Quote a CSV field if it needs it, doubling any quotes inside it
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Policies loaded from a config, with their names
+type NamedPolicies = Vec<(String, Rc<dyn Policy>)>;
+
+/// A problem with a policy config, and the line it was found on
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PolicyConfigError {
+    line: usize,
+    message: String,
+}
+
+impl std::fmt::Display for PolicyConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for PolicyConfigError {}
+
+/// Load named policies from a config, keeping the order they were defined in.
+///
+/// Each non-blank line that doesn't start with `#` defines a policy, like
+/// `strict = all(sled, toboggan)`. A policy is one of:
+/// - `sled` or `toboggan`, the two built in interpretations, which count code points.
+///   Add `(graphemes)`, as in `sled(graphemes)`, to count grapheme clusters instead.
+/// - the name of a policy defined on an earlier line
+/// - `all(p, ...)`, `any(p, ...)` or `exactly(n, p, ...)` of other policies
+fn load_policies(config: &str) -> Result<NamedPolicies, PolicyConfigError> {
+    let mut defined: HashMap<String, Rc<dyn Policy>> = HashMap::new();
+    let mut result = Vec::new();
+
+    for (line_idx, line) in config.lines().enumerate() {
+        let error = |message: String| PolicyConfigError {
+            line: line_idx + 1,
+            message,
+        };
 
-    // Count how many times the letter exists in the password
-    let letter_count = pw.password.chars().filter(|&c| c == pw.letter).count();
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
 
-    (pw.lower <= letter_count) && (letter_count <= pw.upper)
+        let (name, expr) = line
+            .split_once('=')
+            .ok_or_else(|| error("expected `name = policy`".to_string()))?;
+        let name = name.trim();
+        if !is_identifier(name) {
+            return Err(error(format!("`{}` is not a valid policy name", name)));
+        }
+        if defined.contains_key(name) {
+            return Err(error(format!("policy `{}` is defined twice", name)));
+        }
+
+        let mut parser = PolicyParser {
+            src: expr,
+            pos: 0,
+            offset: line[..line.len() - expr.len()].chars().count(),
+            defined: &defined,
+        };
+        let policy = parser.parse_policy().map_err(error)?;
+        parser.skip_whitespace();
+        if parser.pos != expr.len() {
+            return Err(error(format!(
+                "unexpected `{}` after the policy",
+                expr[parser.pos..].trim_end()
+            )));
+        }
+
+        defined.insert(name.to_string(), Rc::clone(&policy));
+        result.push((name.to_string(), policy));
+    }
+    Ok(result)
 }
 
-fn part2_pw_is_valid(pw: &Password) -> bool {
-    // Each policy actually describes two positions in the password, where 1 means the
-    // first character, 2 means the second character, and so on. (Be careful; Toboggan
-    // Corporate Policies have no concept of "index zero"!) Exactly one of these
-    // positions must contain the given letter. Other occurrences of the letter are
-    // irrelevant for the purposes of policy enforcement.
+fn is_identifier(s: &str) -> bool {
+    !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
 
-    // Given the same example list from above:
+/// Parses the policy on the right hand side of one line of a policy config
+struct PolicyParser<'a> {
+    src: &'a str,
+    pos: usize,
+    /// How many characters into its line `src` starts, so errors can point at the right
+    /// column
+    offset: usize,
+    defined: &'a HashMap<String, Rc<dyn Policy>>,
+}
 
-    // 1-3 a: abcde is valid: position 1 contains a and position 3 does not.
-    // 1-3 b: cdefg is invalid: neither position 1 nor position 3 contains b.
-    // 2-9 c: ccccccccc is invalid: both position 2 and position 9 contain c.
+impl<'a> PolicyParser<'a> {
+    /// The column `pos` is at, counting characters from 1 like `ParseError` does
+    fn column(&self) -> usize {
+        self.offset + self.src[..self.pos].chars().count() + 1
+    }
 
-    // How many passwords are valid according to the new interpretation of the policies?
+    fn skip_whitespace(&mut self) {
+        let rest = &self.src[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
 
-    // Convert lower and upper to indices
-    let lower_pos = pw.lower - 1;
-    let upper_pos = pw.upper - 1;
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        if self.src[self.pos..].starts_with(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
 
-    // Check that the upper index actually exist
-    if upper_pos >= pw.password.len() {
-        return false;
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(format!("expected `{}` at column {}", c, self.column()))
+        }
     }
 
-    let char_in_first_pos = pw
-        .password
-        .chars()
-        .nth(lower_pos)
-        .expect("Couldn't get lower character from password")
-        == pw.letter;
+    fn word(&mut self) -> &'a str {
+        self.skip_whitespace();
+        let src = self.src;
+        let rest = &src[self.pos..];
+        let len = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+        self.pos += len;
+        &rest[..len]
+    }
 
-    let char_in_second_pos = pw
-        .password
-        .chars()
-        .nth(upper_pos)
-        .expect("Couldn't get upper character from password")
-        == pw.letter;
+    fn parse_policy(&mut self) -> Result<Rc<dyn Policy>, String> {
+        self.skip_whitespace();
+        let column = self.column();
+        let word = self.word();
+        match word {
+            "" => Err(format!("expected a policy at column {}", column)),
+            "sled" => Ok(Rc::new(SledRental {
+                units: self.parse_units()?,
+            })),
+            "toboggan" => Ok(Rc::new(Toboggan {
+                units: self.parse_units()?,
+            })),
+            "all" => Ok(Rc::new(All(self.parse_arguments()?))),
+            "any" => Ok(Rc::new(Any(self.parse_arguments()?))),
+            "exactly" => {
+                self.expect('(')?;
+                self.skip_whitespace();
+                let column = self.column();
+                let n = self
+                    .word()
+                    .parse()
+                    .map_err(|_| format!("expected a count at column {}", column))?;
+                self.expect(',')?;
+                let policies = self.parse_list()?;
+                Ok(Rc::new(Exactly { n, policies }))
+            }
+            name => self
+                .defined
+                .get(name)
+                .cloned()
+                .ok_or_else(|| format!("unknown policy `{}`", name)),
+        }
+    }
+
+    /// Parse an optional `(code_points)` or `(graphemes)`
+    fn parse_units(&mut self) -> Result<TextUnits, String> {
+        if !self.eat('(') {
+            return Ok(TextUnits::default());
+        }
+        self.skip_whitespace();
+        let column = self.column();
+        let name = self.word();
+        let units = TextUnits::from_name(name)
+            .ok_or_else(|| format!("expected code_points or graphemes at column {}", column))?;
+        self.expect(')')?;
+        Ok(units)
+    }
 
-    let mysum = (char_in_first_pos as usize) + (char_in_second_pos as usize);
+    /// Parse `(p, ...)`
+    fn parse_arguments(&mut self) -> Result<Vec<Rc<dyn Policy>>, String> {
+        self.expect('(')?;
+        self.parse_list()
+    }
 
-    mysum == 1
+    /// Parse `p, ...)`, after the opening bracket
+    fn parse_list(&mut self) -> Result<Vec<Rc<dyn Policy>>, String> {
+        let mut policies = vec![self.parse_policy()?];
+        while self.eat(',') {
+            policies.push(self.parse_policy()?);
+        }
+        self.expect(')')?;
+        Ok(policies)
+    }
 }
 
+const POLICY_CONFIG: &str = "\
+sled = sled
+toboggan = toboggan
+both = all(sled, toboggan)
+either = any(sled, toboggan)
+just_one = exactly(1, sled, toboggan)
+";
+
 fn main() {
     let setup_time = std::time::Instant::now();
     // Read in the input
     let input =
         std::fs::read_to_string("inputs/day02.txt").expect("Could not read day02 input file");
-    let input_lines: Vec<Password> = input.as_str().lines().map(|l| new_password(l)).collect();
+    let input_lines: Vec<Password> = input
+        .lines()
+        .enumerate()
+        .map(|(idx, l)| parse_password(l).unwrap_or_else(|e| panic!("Line {}: {}", idx + 1, e)))
+        .collect();
     println!(
         "Setup took {:.6} microseconds",
         setup_time.elapsed().as_micros()
@@ -134,7 +690,36 @@ fn main() {
 
     println!();
     println!("First puzzle: {}", part1_soln);
-    println!("First puzzle: {}", part2_soln);
+    println!("Second puzzle: {}", part2_soln);
+
+    // Count the passwords that pass some combinations of the two policies
+    let policies = load_policies(POLICY_CONFIG).expect("Could not load the policy config");
+    for (name, policy) in policies.iter().skip(2) {
+        println!(
+            "Valid under {} = {}: {}",
+            name,
+            policy.describe(),
+            count_valid(&input_lines, policy.as_ref())
+        );
+    }
+
+    // With `--audit json` or `--audit csv`, followed by the name of one of the policies
+    // above, explain every rejected password
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(idx) = args.iter().position(|a| a == "--audit") {
+        let name = args.get(idx + 2).map_or("toboggan", String::as_str);
+        let (_, policy) = policies
+            .iter()
+            .find(|(n, _)| n == name)
+            .unwrap_or_else(|| panic!("There is no policy called {}", name));
+        let report = audit(&input_lines, policy.as_ref());
+        println!();
+        match args.get(idx + 1).map(String::as_str) {
+            Some("json") => println!("{}", report.to_json()),
+            Some("csv") => print!("{}", report.to_csv()),
+            _ => panic!("--audit must be followed by json or csv"),
+        }
+    }
 }
 
 #[test]
@@ -144,7 +729,7 @@ fn test_part1() {
     // 1-3 b: cdefg
     // 2-9 c: ccccccccc
     // the result is 2
-    let input: Vec<Password> = vec!["1-3 a: abcde", "1-3 b: cdefg", "2-9 c: ccccccccc"]
+    let input: Vec<Password> = ["1-3 a: abcde", "1-3 b: cdefg", "2-9 c: ccccccccc"]
         .iter()
         .map(|&p| new_password(p))
         .collect();
@@ -159,10 +744,284 @@ fn test_part2() {
     // 1-3 b: cdefg
     // 2-9 c: ccccccccc
     // the result is 1
-    let input: Vec<Password> = vec!["1-3 a: abcde", "1-3 b: cdefg", "2-9 c: ccccccccc"]
+    let input: Vec<Password> = ["1-3 a: abcde", "1-3 b: cdefg", "2-9 c: ccccccccc"]
         .iter()
         .map(|&p| new_password(p))
         .collect();
 
     assert_eq!(part2(&input), 1);
 }
+
+#[test]
+fn test_composed_policies() {
+    let input: Vec<Password> = ["1-3 a: abcde", "1-3 b: cdefg", "2-9 c: ccccccccc"]
+        .iter()
+        .map(|&p| new_password(p))
+        .collect();
+    let sled: Rc<dyn Policy> = Rc::new(SledRental::default());
+    let toboggan: Rc<dyn Policy> = Rc::new(Toboggan::default());
+
+    // Only the first is valid under both, the third only under the sled policy
+    let both = All(vec![Rc::clone(&sled), Rc::clone(&toboggan)]);
+    assert_eq!(count_valid(&input, &both), 1);
+    let either = Any(vec![Rc::clone(&sled), Rc::clone(&toboggan)]);
+    assert_eq!(count_valid(&input, &either), 2);
+    let just_one = Exactly {
+        n: 1,
+        policies: vec![sled, toboggan],
+    };
+    assert_eq!(count_valid(&input, &just_one), 1);
+    assert!(just_one.is_valid(&input[2]));
+}
+
+#[test]
+fn test_load_policies() {
+    let config = "# Some policies
+both = all(sled, toboggan)
+
+neither = exactly( 0 , sled,toboggan )
+either_or_neither = any(both, neither, exactly(1, sled, toboggan))
+";
+    let policies = load_policies(config).expect("Could not load policies");
+    let described: Vec<(&str, String)> = policies
+        .iter()
+        .map(|(name, p)| (name.as_str(), p.describe()))
+        .collect();
+    assert_eq!(
+        described,
+        vec![
+            ("both", "all(sled, toboggan)".to_string()),
+            ("neither", "exactly(0, sled, toboggan)".to_string()),
+            (
+                "either_or_neither",
+                "any(all(sled, toboggan), exactly(0, sled, toboggan), exactly(1, sled, toboggan))"
+                    .to_string()
+            ),
+        ]
+    );
+
+    let input: Vec<Password> = ["1-3 a: abcde", "1-3 b: cdefg", "2-9 c: ccccccccc"]
+        .iter()
+        .map(|&p| new_password(p))
+        .collect();
+    assert_eq!(count_valid(&input, policies[1].1.as_ref()), 1);
+    assert_eq!(count_valid(&input, policies[2].1.as_ref()), 3);
+}
+
+#[test]
+fn test_load_policies_errors() {
+    let error_for = |config: &str| load_policies(config).err().map(|e| e.to_string());
+    assert_eq!(
+        error_for("a = sled\nb = nope"),
+        Some("line 2: unknown policy `nope`".to_string())
+    );
+    assert_eq!(
+        error_for("a = all(sled"),
+        Some("line 1: expected `)` at column 13".to_string())
+    );
+    assert_eq!(
+        error_for("a = exactly(x, sled)"),
+        Some("line 1: expected a count at column 13".to_string())
+    );
+    assert_eq!(
+        error_for("a = sled toboggan"),
+        Some("line 1: unexpected `toboggan` after the policy".to_string())
+    );
+    assert_eq!(
+        error_for("a = sled\na = toboggan"),
+        Some("line 2: policy `a` is defined twice".to_string())
+    );
+    assert_eq!(
+        error_for("a = any()"),
+        Some("line 1: expected a policy at column 9".to_string())
+    );
+    assert_eq!(
+        error_for("sled"),
+        Some("line 1: expected `name = policy`".to_string())
+    );
+    // Columns count characters, so wide whitespace doesn't push them along
+    assert_eq!(
+        error_for("a = all(\u{3000}sled"),
+        Some("line 1: expected `)` at column 14".to_string())
+    );
+    assert_eq!(
+        error_for("a =\u{3000}any(\u{3000})"),
+        Some("line 1: expected a policy at column 10".to_string())
+    );
+}
+
+#[test]
+fn test_non_ascii_passwords() {
+    let sled = SledRental::default();
+    let toboggan = Toboggan::default();
+
+    // Multi-byte letters are a single letter each
+    let pw = new_password("1-3 é: éaé");
+    assert_eq!(pw.letter, "é");
+    assert!(sled.is_valid(&pw));
+    assert!(!toboggan.is_valid(&pw));
+
+    let pw = new_password("2-4 🦀: a🦀bc🦀");
+    assert!(sled.is_valid(&pw));
+    assert!(toboggan.is_valid(&pw));
+
+    // "é" is 2 bytes, so there are 3 bytes, but only 2 letters, and no third position
+    let pw = new_password("1-3 a: aé");
+    assert!(!toboggan.is_valid(&pw));
+    let pw = new_password("2-2 é: aé");
+    assert!(!toboggan.is_valid(&pw));
+
+    // There is no position 0
+    let pw = new_password("0-1 a: ba");
+    assert!(!toboggan.is_valid(&pw));
+}
+
+#[test]
+fn test_grapheme_passwords() {
+    let code_points = Toboggan::default();
+    let graphemes = Toboggan {
+        units: TextUnits::Graphemes,
+    };
+
+    // "e" followed by a combining acute accent is two code points, but one grapheme
+    let pw = new_password("2-3 e\u{301}: ae\u{301}b");
+    assert_eq!(pw.letter, "e\u{301}");
+    assert_eq!(
+        code_points.check(&pw).map_err(|v| v.observed),
+        Err(Observed::LetterUnits(2))
+    );
+    assert!(graphemes.is_valid(&pw));
+
+    // By code points, the "e" is in position 2, but by graphemes, it's part of "é"
+    let pw = new_password("2-4 e: ae\u{301}bc");
+    assert!(code_points.is_valid(&pw));
+    assert!(!graphemes.is_valid(&pw));
+
+    let sled = SledRental {
+        units: TextUnits::Graphemes,
+    };
+    let pw = new_password("1-1 e: e\u{301}e");
+    assert!(sled.is_valid(&pw));
+    assert!(!SledRental::default().is_valid(&pw));
+}
+
+#[test]
+fn test_load_policies_units() {
+    let config = "graphemes = all(sled(graphemes), toboggan( graphemes ))
+code_points = toboggan(code_points)";
+    let policies = load_policies(config).expect("Could not load policies");
+    assert_eq!(
+        policies[0].1.describe(),
+        "all(sled(graphemes), toboggan(graphemes))"
+    );
+    assert_eq!(policies[1].1.describe(), "toboggan");
+
+    assert_eq!(
+        load_policies("a = sled(bytes)")
+            .err()
+            .map(|e| e.to_string()),
+        Some("line 1: expected code_points or graphemes at column 10".to_string())
+    );
+}
+
+#[test]
+fn test_violations() {
+    let sled = SledRental::default();
+    let toboggan = Toboggan::default();
+
+    assert_eq!(
+        sled.check(&new_password("1-3 b: cdefg")),
+        Err(Violation {
+            policy: "sled".to_string(),
+            observed: Observed::Count(0),
+            reason: "b appears fewer than 1 times".to_string(),
+        })
+    );
+    assert_eq!(
+        toboggan.check(&new_password("2-9 c: ccccccccc")),
+        Err(Violation {
+            policy: "toboggan".to_string(),
+            observed: Observed::Positions {
+                first: Some("c".to_string()),
+                second: Some("c".to_string()),
+            },
+            reason: "both positions hold c".to_string(),
+        })
+    );
+
+    // The lower position can be past the end too
+    let violation = toboggan
+        .check(&new_password("5-1 a: abc"))
+        .expect_err("Position 5 does not exist");
+    assert_eq!(
+        violation.observed,
+        Observed::Positions {
+            first: None,
+            second: Some("a".to_string()),
+        }
+    );
+
+    let both = All(vec![Rc::new(sled), Rc::new(toboggan)]);
+    let violation = both
+        .check(&new_password("2-9 c: ccccccccc"))
+        .expect_err("Toboggan policy fails");
+    assert_eq!(violation.observed, Observed::Passed { passed: 1, of: 2 });
+    assert_eq!(
+        violation.reason,
+        "toboggan failed: both positions hold c".to_string()
+    );
+
+    // A letter that is more than one unit is reported as such, rather than as missing
+    assert_eq!(
+        sled.check(&new_password("1-3 ab: abab")),
+        Err(Violation {
+            policy: "sled".to_string(),
+            observed: Observed::LetterUnits(2),
+            reason: "the letter ab is 2 code points, not one".to_string(),
+        })
+    );
+    let violation = Toboggan {
+        units: TextUnits::Graphemes,
+    }
+    .check(&new_password("1-2 ab: ab"))
+    .expect_err("ab is two graphemes");
+    assert_eq!(
+        violation.reason,
+        "the letter ab is 2 grapheme clusters, not one"
+    );
+}
+
+#[test]
+fn test_audit_report() {
+    let input: Vec<Password> = ["1-3 a: abcde", "1-3 b: cdefg", "2-9 c: ccccccccc"]
+        .iter()
+        .map(|&p| new_password(p))
+        .collect();
+    let report = audit(&input, &Toboggan::default());
+    let lines: Vec<usize> = report.rejections.iter().map(|r| r.line).collect();
+    assert_eq!(lines, vec![2, 3]);
+
+    assert_eq!(
+        report.to_csv(),
+        "line,password,letter,lower,upper,policy,observed,reason
+2,cdefg,b,1,3,toboggan,\"positions c, e\",neither position holds b
+3,ccccccccc,c,2,9,toboggan,\"positions c, c\",both positions hold c
+"
+    );
+
+    let json: serde_json::Value =
+        serde_json::from_str(&report.to_json()).expect("Report is not valid JSON");
+    assert_eq!(json.as_array().map(Vec::len), Some(2));
+    assert_eq!(json[0]["line"], 2);
+    assert_eq!(json[0]["policy"], "toboggan");
+    assert_eq!(json[0]["observed"]["positions"][1]["position"], 3);
+    assert_eq!(json[0]["observed"]["positions"][1]["letter"], "e");
+    assert_eq!(json[1]["reason"], "both positions hold c");
+}
+
+#[test]
+fn test_csv_field() {
+    assert_eq!(csv_field("abc"), "abc");
+    assert_eq!(csv_field("a,b"), "\"a,b\"");
+    assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+}
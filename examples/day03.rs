@@ -1,39 +1,288 @@
-fn create_tree_field(input: &str) -> Vec<Vec<bool>> {
-    let mut result = Vec::new();
-    for row in input.lines() {
-        result.push(row.chars().map(|c| c == '#').collect());
+use std::ops::RangeInclusive;
+
+use rs_aoc::grid::TreeField;
+
+/// How far the toboggan moves on each step. A negative `right` moves it left.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Stride {
+    right: isize,
+    down: usize,
+}
+
+impl Stride {
+    /// The column reached after `step` steps on a map `width` columns wide that repeats
+    /// forever to either side. `right` is reduced modulo the width first, so this works
+    /// however far the toboggan goes without the column overflowing.
+    fn wrapped_col(self, step: usize, width: usize) -> usize {
+        let right = self.right.rem_euclid(width as isize) as u128;
+        ((step % width) as u128 * right % width as u128) as usize
     }
-    result
 }
 
-fn count_n_trees_hit(field: &[Vec<bool>], stride: &Stride) -> usize {
-    // Get the number of rows and columns in the field
-    let nrows = field.len();
-    let ncols = field[0].len();
+/// What happens when the toboggan goes past the left or right edge of the map
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Edges {
+    /// The map repeats forever to either side
+    Wrap,
+    /// The ride ends at the edge
+    Stop,
+}
 
-    // Calculate the indices of all visited points
-    let col_indices: Vec<usize> = (0..nrows).map(|i| (i * stride.right) % ncols).collect();
+/// A position the toboggan visits
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Visit {
+    /// How many steps it took to get here
+    step: usize,
+    row: usize,
+    /// The column within the map, after wrapping around
+    col: usize,
+    tree: bool,
+}
 
-    let row_indices: Vec<usize> = (0..nrows).step_by(stride.down).collect();
+/// Walks down the map from the top left, one stride at a time, until it goes off the
+/// bottom (or the side, if the edges don't wrap)
+struct Path<'a> {
+    field: &'a TreeField,
+    stride: Stride,
+    edges: Edges,
+    step: usize,
+}
 
-    // Filter if they are true, then count()
-    col_indices
-        .iter()
-        .zip(row_indices.iter())
-        .filter(|(&col, &row)| field[row][col])
+impl Iterator for Path<'_> {
+    type Item = Visit;
+
+    fn next(&mut self) -> Option<Visit> {
+        let row = self.step.checked_mul(self.stride.down)?;
+        if row >= self.field.height() || self.field.width() == 0 {
+            return None;
+        }
+        let col = match self.edges {
+            Edges::Wrap => self.stride.wrapped_col(self.step, self.field.width()),
+            // A column too big to work out is well past the edge
+            Edges::Stop => (self.step as isize)
+                .checked_mul(self.stride.right)
+                .and_then(|col| usize::try_from(col).ok())
+                .filter(|&col| col < self.field.width())?,
+        };
+
+        let visit = Visit {
+            step: self.step,
+            row,
+            col,
+            tree: self.field.get_wrapping(row, col),
+        };
+        self.step += 1;
+        Some(visit)
+    }
+}
+
+/// Every position visited going down the map with the given stride, starting from the
+/// top left
+fn path(field: &TreeField, stride: Stride, edges: Edges) -> Path<'_> {
+    assert!(stride.down > 0, "The toboggan must move down on every step");
+    Path {
+        field,
+        stride,
+        edges,
+        step: 0,
+    }
+}
+
+fn count_n_trees_hit(field: &TreeField, stride: &Stride) -> usize {
+    path(field, *stride, Edges::Wrap)
+        .filter(|visit| visit.tree)
         .count()
 }
 
-struct Stride {
-    right: usize,
-    down: usize,
+/// Count the trees hit on each of the slopes, wrapping around the edges, in one pass
+/// over the rows of the field
+fn count_all_slopes(field: &TreeField, strides: &[Stride]) -> Vec<usize> {
+    assert!(
+        strides.iter().all(|s| s.down > 0),
+        "The toboggan must move down on every step"
+    );
+    let mut counts = vec![0; strides.len()];
+    if field.width() == 0 {
+        return counts;
+    }
+
+    for row in 0..field.height() {
+        // Each slope only lands on the rows that are a whole number of steps down
+        for (stride, count) in strides.iter().zip(counts.iter_mut()) {
+            if row % stride.down != 0 {
+                continue;
+            }
+            let col = stride.wrapped_col(row / stride.down, field.width());
+            if field.get_wrapping(row, col) {
+                *count += 1;
+            }
+        }
+    }
+    counts
+}
+
+/// Whether the best slopes hit the fewest trees, or the most
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Goal {
+    FewestTrees,
+    MostTrees,
+}
+
+/// How many trees a slope hits
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct RankedSlope {
+    stride: Stride,
+    trees: usize,
+}
+
+/// Try every slope with `right` and `down` in the given ranges, and rank them from best
+/// to worst. Slopes that hit the same number of trees stay in order of `right`, then
+/// `down`.
+fn rank_slopes(
+    field: &TreeField,
+    rights: RangeInclusive<isize>,
+    downs: RangeInclusive<usize>,
+    goal: Goal,
+) -> Vec<RankedSlope> {
+    let strides: Vec<Stride> = rights
+        .flat_map(|right| downs.clone().map(move |down| Stride { right, down }))
+        .collect();
+    let mut ranking: Vec<RankedSlope> = strides
+        .iter()
+        .zip(count_all_slopes(field, &strides))
+        .map(|(&stride, trees)| RankedSlope { stride, trees })
+        .collect();
+    match goal {
+        Goal::FewestTrees => ranking.sort_by_key(|r| r.trees),
+        Goal::MostTrees => ranking.sort_by_key(|r| std::cmp::Reverse(r.trees)),
+    }
+    ranking
+}
+
+/// What to draw for one cell of a rendered map
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Cell {
+    Open,
+    Tree,
+    /// Open ground the toboggan passed through
+    Visited,
+    /// A tree the toboggan hit
+    Hit,
+}
+
+impl Cell {
+    /// The character the puzzle uses for the cell
+    fn symbol(self) -> char {
+        match self {
+            Cell::Open => '.',
+            Cell::Tree => '#',
+            Cell::Visited => 'O',
+            Cell::Hit => 'X',
+        }
+    }
+
+    fn colour(self) -> &'static str {
+        match self {
+            Cell::Open => "#f4f1e8",
+            Cell::Tree => "#2e7d32",
+            Cell::Visited => "#1e88e5",
+            Cell::Hit => "#d32f2f",
+        }
+    }
+}
+
+/// Lay the path of the toboggan over the map, repeating the map to the left and right as
+/// many times as the path needs
+fn overlay(field: &TreeField, stride: Stride) -> Vec<Vec<Cell>> {
+    let width = field.width() as isize;
+    let visits: Vec<Visit> = path(field, stride, Edges::Wrap).collect();
+
+    // Work out which copies of the map the path goes through. Copy 0 is the original.
+    let unwrapped_col = |v: &Visit| v.step as isize * stride.right;
+    let first_copy = visits
+        .iter()
+        .map(|v| unwrapped_col(v).div_euclid(width.max(1)))
+        .min()
+        .unwrap_or(0);
+    let last_copy = visits
+        .iter()
+        .map(|v| unwrapped_col(v).div_euclid(width.max(1)))
+        .max()
+        .unwrap_or(0);
+    let left_edge = first_copy * width;
+    let total_width = ((last_copy - first_copy + 1) * width) as usize;
+
+    let mut cells: Vec<Vec<Cell>> = (0..field.height())
+        .map(|row| {
+            (0..total_width)
+                .map(|col| {
+                    let col = (left_edge + col as isize).rem_euclid(width) as usize;
+                    if field.get_wrapping(row, col) {
+                        Cell::Tree
+                    } else {
+                        Cell::Open
+                    }
+                })
+                .collect()
+        })
+        .collect();
+    for visit in &visits {
+        let col = (unwrapped_col(visit) - left_edge) as usize;
+        cells[visit.row][col] = if visit.tree { Cell::Hit } else { Cell::Visited };
+    }
+    cells
+}
+
+/// Draw the map with the path on it as text, marking open ground the toboggan passes
+/// through with `O`, and trees it hits with `X`
+fn render_text(field: &TreeField, stride: Stride) -> String {
+    overlay(field, stride)
+        .iter()
+        .map(|row| row.iter().map(|c| c.symbol()).collect::<String>() + "\n")
+        .collect()
+}
+
+/// Draw the map with the path on it as an SVG image
+fn render_svg(field: &TreeField, stride: Stride) -> String {
+    const CELL_SIZE: usize = 8;
+    let cells = overlay(field, stride);
+    let width = cells.first().map_or(0, Vec::len) * CELL_SIZE;
+    let height = cells.len() * CELL_SIZE;
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">\n",
+        w = width,
+        h = height
+    );
+    svg += &format!(
+        "<rect width=\"{}\" height=\"{}\" fill=\"{}\"/>\n",
+        width,
+        height,
+        Cell::Open.colour()
+    );
+    for (row_idx, row) in cells.iter().enumerate() {
+        for (col_idx, &cell) in row.iter().enumerate() {
+            if cell == Cell::Open {
+                continue;
+            }
+            svg += &format!(
+                "<rect x=\"{}\" y=\"{}\" width=\"{s}\" height=\"{s}\" fill=\"{}\"/>\n",
+                col_idx * CELL_SIZE,
+                row_idx * CELL_SIZE,
+                cell.colour(),
+                s = CELL_SIZE
+            );
+        }
+    }
+    svg += "</svg>\n";
+    svg
 }
 
 fn main() {
     let setup_time = std::time::Instant::now();
     let input_str =
         std::fs::read_to_string("inputs/day03.txt").expect("Could not read day 3 input");
-    let field = create_tree_field(input_str.as_str());
+    let field = TreeField::parse(input_str.as_str());
     println!(
         "Setup took {:.6} microseconds",
         setup_time.elapsed().as_micros()
@@ -49,17 +298,14 @@ fn main() {
     );
 
     let part2_time = std::time::Instant::now();
-    let part2_strides = vec![
+    let part2_strides = [
         Stride { right: 1, down: 1 },
         Stride { right: 3, down: 1 },
         Stride { right: 5, down: 1 },
         Stride { right: 7, down: 1 },
         Stride { right: 1, down: 2 },
     ];
-    let part2_solution: usize = part2_strides
-        .iter()
-        .map(|s| count_n_trees_hit(&field, s))
-        .product();
+    let part2_solution: usize = count_all_slopes(&field, &part2_strides).iter().product();
     println!(
         "Part 2 took {:.6} microseconds",
         part2_time.elapsed().as_micros()
@@ -68,6 +314,51 @@ fn main() {
     println!();
     println!("First puzzle: {}", part1_soln);
     println!("Second puzzle: {}", part2_solution);
+
+    // Without the map repeating, the first slope runs off the right edge early
+    let unwrapped: Vec<Visit> = path(&field, part1_stride, Edges::Stop).collect();
+    println!(
+        "Without wrapping, the first slope hits {} trees in {} steps",
+        unwrapped.iter().filter(|v| v.tree).count(),
+        unwrapped.len()
+    );
+
+    // Look for the slopes that hit the fewest and the most trees among the small ones
+    for goal in [Goal::FewestTrees, Goal::MostTrees] {
+        let search_time = std::time::Instant::now();
+        let ranking = rank_slopes(&field, -7..=7, 1..=3, goal);
+        let RankedSlope { stride, trees } = ranking[0];
+        println!(
+            "{:?}: right {}, down {}, hitting {} trees (ranking {} slopes took {} microseconds)",
+            goal,
+            stride.right,
+            stride.down,
+            trees,
+            ranking.len(),
+            search_time.elapsed().as_micros()
+        );
+    }
+
+    // With `--render <right> <down> [file.svg]`, draw the path for that slope, and also
+    // save it as an SVG if a file is given
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(idx) = args.iter().position(|a| a == "--render") {
+        let arg = |offset: usize, name: &str| -> String {
+            args.get(idx + offset)
+                .unwrap_or_else(|| panic!("--render needs a {} value", name))
+                .clone()
+        };
+        let stride = Stride {
+            right: arg(1, "right").parse().expect("Could not parse right"),
+            down: arg(2, "down").parse().expect("Could not parse down"),
+        };
+        println!();
+        print!("{}", render_text(&field, stride));
+        if let Some(file) = args.get(idx + 3) {
+            std::fs::write(file, render_svg(&field, stride)).expect("Could not write the SVG");
+            println!("Wrote {}", file);
+        }
+    }
 }
 
 #[test]
@@ -119,9 +410,13 @@ fn test_read_array() {
         ],
     ];
 
-    let got = create_tree_field(input_str);
-    for (got_row, expected_row) in got.iter().zip(expected.iter()) {
-        assert_eq!(got_row, expected_row);
+    let got = TreeField::parse(input_str);
+    assert_eq!(got.height(), expected.len());
+    assert_eq!(got.width(), expected[0].len());
+    for (row, expected_row) in expected.iter().enumerate() {
+        for (col, &expected_cell) in expected_row.iter().enumerate() {
+            assert_eq!(got.get(row, col), Some(expected_cell));
+        }
     }
 }
 
@@ -138,7 +433,7 @@ fn test_part1() {
 #.##...#...
 #...##....#
 .#..#...#.#";
-    let field = create_tree_field(input_str);
+    let field = TreeField::parse(input_str);
     let stride = Stride { right: 3, down: 1 };
     let n_trees_hit = count_n_trees_hit(&field, &stride);
     assert_eq!(n_trees_hit, 7);
@@ -157,8 +452,8 @@ fn test_part2() {
 #.##...#...
 #...##....#
 .#..#...#.#";
-    let field = create_tree_field(input_str);
-    let part2_strides = vec![
+    let field = TreeField::parse(input_str);
+    let part2_strides = [
         Stride { right: 1, down: 1 },
         Stride { right: 3, down: 1 },
         Stride { right: 5, down: 1 },
@@ -171,3 +466,296 @@ fn test_part2() {
         .product();
     assert_eq!(part2_solution, 336);
 }
+
+#[test]
+fn test_path() {
+    let field = TreeField::parse(
+        "..#
+.#.
+#..
+..#",
+    );
+    let visited: Vec<(usize, usize, bool)> =
+        path(&field, Stride { right: 1, down: 1 }, Edges::Wrap)
+            .map(|v| (v.row, v.col, v.tree))
+            .collect();
+    assert_eq!(
+        visited,
+        vec![(0, 0, false), (1, 1, true), (2, 2, false), (3, 0, false)]
+    );
+
+    // Every other row, so the step count and row differ
+    let visited: Vec<Visit> = path(&field, Stride { right: 1, down: 2 }, Edges::Wrap).collect();
+    assert_eq!(
+        visited,
+        vec![
+            Visit {
+                step: 0,
+                row: 0,
+                col: 0,
+                tree: false
+            },
+            Visit {
+                step: 1,
+                row: 2,
+                col: 1,
+                tree: false
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_path_left_and_straight_down() {
+    let field = TreeField::parse(
+        "..#
+.#.
+#..
+..#",
+    );
+
+    // Moving left wraps around to the right hand side
+    let cols: Vec<usize> = path(&field, Stride { right: -1, down: 1 }, Edges::Wrap)
+        .map(|v| v.col)
+        .collect();
+    assert_eq!(cols, vec![0, 2, 1, 0]);
+
+    let cols: Vec<usize> = path(&field, Stride { right: 0, down: 1 }, Edges::Wrap)
+        .map(|v| v.col)
+        .collect();
+    assert_eq!(cols, vec![0, 0, 0, 0]);
+}
+
+#[test]
+fn test_path_huge_right() {
+    let field = TreeField::parse(
+        "###
+###
+###
+###",
+    );
+
+    // Wrapping never ends the ride early, however far each step goes
+    for right in [isize::MAX, isize::MIN, isize::MAX - 1, isize::MIN + 1] {
+        assert_eq!(
+            count_n_trees_hit(&field, &Stride { right, down: 1 }),
+            4,
+            "for right {}",
+            right
+        );
+        let expected: Vec<usize> = (0..4)
+            .map(|step| (step as i128 * right as i128).rem_euclid(3) as usize)
+            .collect();
+        let cols: Vec<usize> = path(&field, Stride { right, down: 1 }, Edges::Wrap)
+            .map(|v| v.col)
+            .collect();
+        assert_eq!(cols, expected, "for right {}", right);
+    }
+
+    // Without wrapping, the ride still ends at the edge
+    assert_eq!(
+        path(
+            &field,
+            Stride {
+                right: isize::MAX,
+                down: 1
+            },
+            Edges::Stop
+        )
+        .count(),
+        1
+    );
+}
+
+#[test]
+fn test_path_stop_at_edges() {
+    let field = TreeField::parse(
+        "..#
+.#.
+#..
+..#",
+    );
+
+    // Leaves the right edge after column 2
+    let cols: Vec<usize> = path(&field, Stride { right: 2, down: 1 }, Edges::Stop)
+        .map(|v| v.col)
+        .collect();
+    assert_eq!(cols, vec![0, 2]);
+
+    // Moving left from the top left corner leaves straight away
+    assert_eq!(
+        path(&field, Stride { right: -1, down: 1 }, Edges::Stop).count(),
+        1
+    );
+}
+
+#[test]
+fn test_left_is_mirrored_right() {
+    let input_str = "..##.......
+#...#...#..
+.#....#..#.
+..#.#...#.#
+.#...##..#.
+..#.##.....
+.#.#.#....#
+.#........#
+#.##...#...
+#...##....#
+.#..#...#.#";
+    // Flip each row, then move the first column back to the front, so that column 0
+    // stays put and everything else is mirrored around it
+    let mirrored: String = input_str
+        .lines()
+        .map(|row| {
+            let (first, rest) = row.split_at(1);
+            format!("{}{}", first, rest.chars().rev().collect::<String>())
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let field = TreeField::parse(input_str);
+    let mirrored = TreeField::parse(&mirrored);
+    for right in 0..=7 {
+        for down in 1..=2 {
+            assert_eq!(
+                count_n_trees_hit(&field, &Stride { right, down }),
+                count_n_trees_hit(
+                    &mirrored,
+                    &Stride {
+                        right: -right,
+                        down
+                    }
+                ),
+            );
+        }
+    }
+}
+
+#[test]
+fn test_count_all_slopes() {
+    let input_str = "..##.......
+#...#...#..
+.#....#..#.
+..#.#...#.#
+.#...##..#.
+..#.##.....
+.#.#.#....#
+.#........#
+#.##...#...
+#...##....#
+.#..#...#.#";
+    let field = TreeField::parse(input_str);
+    let extremes = [isize::MIN, isize::MIN + 1, isize::MAX - 1, isize::MAX];
+    let strides: Vec<Stride> = (-12..=12)
+        .chain(extremes)
+        .flat_map(|right| (1..=4).map(move |down| Stride { right, down }))
+        .collect();
+    let expected: Vec<usize> = strides
+        .iter()
+        .map(|s| count_n_trees_hit(&field, s))
+        .collect();
+    assert_eq!(count_all_slopes(&field, &strides), expected);
+}
+
+#[test]
+fn test_rank_slopes() {
+    let input_str = "..##.......
+#...#...#..
+.#....#..#.
+..#.#...#.#
+.#...##..#.
+..#.##.....
+.#.#.#....#
+.#........#
+#.##...#...
+#...##....#
+.#..#...#.#";
+    let field = TreeField::parse(input_str);
+
+    // The slopes from part 2 hit 2, 7, 3, 4 and 2 trees
+    let ranking = rank_slopes(&field, 1..=7, 1..=2, Goal::MostTrees);
+    assert_eq!(ranking.len(), 14);
+    assert_eq!(
+        ranking[0],
+        RankedSlope {
+            stride: Stride { right: 3, down: 1 },
+            trees: 7
+        }
+    );
+    assert!(ranking.windows(2).all(|w| w[0].trees >= w[1].trees));
+
+    let ranking = rank_slopes(&field, 1..=7, 1..=2, Goal::FewestTrees);
+    assert!(ranking.windows(2).all(|w| w[0].trees <= w[1].trees));
+    let fewest = ranking[0].trees;
+    for ranked in &ranking {
+        assert_eq!(ranked.trees, count_n_trees_hit(&field, &ranked.stride));
+        assert!(ranked.trees >= fewest);
+    }
+}
+
+#[test]
+fn test_render_text() {
+    // The example from the puzzle, which repeats the map to the right
+    let input_str = "..##.......
+#...#...#..
+.#....#..#.
+..#.#...#.#
+.#...##..#.
+..#.##.....
+.#.#.#....#
+.#........#
+#.##...#...
+#...##....#
+.#..#...#.#";
+    let field = TreeField::parse(input_str);
+    // The puzzle draws more copies of the map than it needs. The path ends in column 30,
+    // in the third copy, so exactly three copies are drawn here.
+    let expected = "\
+O.##.........##.........##.......
+#..O#...#..#...#...#..#...#...#..
+.#....X..#..#....#..#..#....#..#.
+..#.#...#O#..#.#...#.#..#.#...#.#
+.#...##..#..X...##..#..#...##..#.
+..#.##.......#.X#.......#.##.....
+.#.#.#....#.#.#.#.O..#.#.#.#....#
+.#........#.#........X.#........#
+#.##...#...#.##...#...#.X#...#...
+#...##....##...##....##...#X....#
+.#..#...#.#.#..#...#.#.#..#...X.#
+";
+    let got = render_text(&field, Stride { right: 3, down: 1 });
+    assert_eq!(got, expected);
+    assert!(got.lines().all(|row| row.len() == 3 * field.width()));
+}
+
+#[test]
+fn test_render_text_left() {
+    let field = TreeField::parse(
+        "..#
+.#.
+#..",
+    );
+    // Going left, the copy of the map to the left of the original is drawn first. The
+    // only tree on the path is the one at the start of the last row, which is missed.
+    assert_eq!(
+        render_text(&field, Stride { right: -1, down: 1 }),
+        "..#O.#
+.#O.#.
+#O.#..
+"
+    );
+}
+
+#[test]
+fn test_render_svg() {
+    let field = TreeField::parse(
+        "..#
+.#.",
+    );
+    let svg = render_svg(&field, Stride { right: 1, down: 1 });
+    assert!(svg.starts_with("<svg "));
+    assert!(svg.ends_with("</svg>\n"));
+    // The background, the one tree that isn't hit, and the two cells on the path
+    assert_eq!(svg.matches("<rect").count(), 4);
+    assert!(svg.contains(&format!("fill=\"{}\"", Cell::Hit.colour())));
+}
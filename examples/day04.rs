@@ -0,0 +1,449 @@
+use rs_aoc::records::{records, Record};
+use std::ops::RangeInclusive;
+
+/// What the value of a field has to look like
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Rule {
+    /// Anything goes
+    Any,
+    /// A four digit year in the range
+    Year(RangeInclusive<u32>),
+    /// A number followed by one of the units, in the range for that unit
+    Measure(&'static [(&'static str, RangeInclusive<u32>)]),
+    /// `#` followed by six lowercase hex digits
+    HexColour,
+    /// One of the words
+    OneOf(&'static [&'static str]),
+    /// Exactly this many digits, leading zeros included
+    Digits(usize),
+}
+
+impl Rule {
+    /// Check a value against the rule, saying what is wrong with it if it doesn't pass
+    fn check(&self, value: &str) -> Result<(), String> {
+        let all_digits = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+        match self {
+            Rule::Any => Ok(()),
+            Rule::Year(range) => {
+                if value.len() != 4 || !all_digits(value) {
+                    return Err("not a four digit year".to_string());
+                }
+                let year: u32 = value.parse().expect("Four digits always fit in a u32");
+                if range.contains(&year) {
+                    Ok(())
+                } else {
+                    Err(format!("not between {} and {}", range.start(), range.end()))
+                }
+            }
+            Rule::Measure(units) => {
+                let split = value
+                    .find(|c: char| !c.is_ascii_digit())
+                    .unwrap_or(value.len());
+                let (number, unit) = value.split_at(split);
+                let (_, range) = units
+                    .iter()
+                    .find(|(name, _)| *name == unit)
+                    .ok_or_else(|| {
+                        let names: Vec<&str> = units.iter().map(|(name, _)| *name).collect();
+                        format!("unit is not one of {}", names.join(", "))
+                    })?;
+                let amount: u32 = match number.parse() {
+                    Ok(amount) if all_digits(number) => amount,
+                    _ => return Err("not a number followed by a unit".to_string()),
+                };
+                if range.contains(&amount) {
+                    Ok(())
+                } else {
+                    Err(format!(
+                        "not between {}{unit} and {}{unit}",
+                        range.start(),
+                        range.end(),
+                        unit = unit
+                    ))
+                }
+            }
+            Rule::HexColour => match value.strip_prefix('#') {
+                Some(hex)
+                    if hex.len() == 6
+                        && hex.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f')) =>
+                {
+                    Ok(())
+                }
+                _ => Err("not # followed by six hex digits".to_string()),
+            },
+            Rule::OneOf(words) => {
+                if words.contains(&value) {
+                    Ok(())
+                } else {
+                    Err(format!("not one of {}", words.join(", ")))
+                }
+            }
+            Rule::Digits(n) => {
+                if value.len() == *n && all_digits(value) {
+                    Ok(())
+                } else {
+                    Err(format!("not {} digits", n))
+                }
+            }
+        }
+    }
+}
+
+/// One field a passport can have
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct FieldSpec {
+    key: &'static str,
+    required: bool,
+    rule: Rule,
+}
+
+/// The fields of a passport, as the puzzle describes them
+const PASSPORT_SCHEMA: &[FieldSpec] = &[
+    FieldSpec {
+        key: "byr",
+        required: true,
+        rule: Rule::Year(1920..=2002),
+    },
+    FieldSpec {
+        key: "iyr",
+        required: true,
+        rule: Rule::Year(2010..=2020),
+    },
+    FieldSpec {
+        key: "eyr",
+        required: true,
+        rule: Rule::Year(2020..=2030),
+    },
+    FieldSpec {
+        key: "hgt",
+        required: true,
+        rule: Rule::Measure(&[("cm", 150..=193), ("in", 59..=76)]),
+    },
+    FieldSpec {
+        key: "hcl",
+        required: true,
+        rule: Rule::HexColour,
+    },
+    FieldSpec {
+        key: "ecl",
+        required: true,
+        rule: Rule::OneOf(&["amb", "blu", "brn", "gry", "grn", "hzl", "oth"]),
+    },
+    FieldSpec {
+        key: "pid",
+        required: true,
+        rule: Rule::Digits(9),
+    },
+    FieldSpec {
+        key: "cid",
+        required: false,
+        rule: Rule::Any,
+    },
+];
+
+/// The `key:value` pairs of one passport, in the order they were written
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Passport<'a> {
+    /// The line number the passport starts on, starting from 1
+    line: usize,
+    fields: Vec<(&'a str, &'a str)>,
+    /// Anything that wasn't a `key:value` pair
+    malformed: Vec<&'a str>,
+}
+
+impl<'a> Passport<'a> {
+    fn new(record: &Record<&'a str>) -> Self {
+        let mut passport = Passport {
+            line: record.line,
+            fields: Vec::new(),
+            malformed: Vec::new(),
+        };
+        for token in record.lines.iter().flat_map(|l| l.split_whitespace()) {
+            match token.split_once(':') {
+                Some(pair) => passport.fields.push(pair),
+                None => passport.malformed.push(token),
+            }
+        }
+        passport
+    }
+}
+
+/// Read the passports, which are separated by blank lines
+fn parse_passports(input: &str) -> Vec<Passport<'_>> {
+    records(input).map(|r| Passport::new(&r)).collect()
+}
+
+/// Something wrong with a passport
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Problem<'a> {
+    /// A required field isn't there
+    Missing(&'static str),
+    /// A field is there, but its value breaks the field's rule
+    Invalid {
+        key: &'a str,
+        value: &'a str,
+        reason: String,
+    },
+    /// A field is there more than once
+    Repeated(&'a str),
+    /// A field isn't in the schema
+    Unknown(&'a str),
+    /// Some text isn't a `key:value` pair
+    Malformed(&'a str),
+}
+
+impl std::fmt::Display for Problem<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Problem::Missing(key) => write!(f, "{} is missing", key),
+            Problem::Invalid { key, value, reason } => {
+                write!(f, "{} {:?} is {}", key, value, reason)
+            }
+            Problem::Repeated(key) => write!(f, "{} is given more than once", key),
+            Problem::Unknown(key) => write!(f, "{} is not a passport field", key),
+            Problem::Malformed(token) => write!(f, "{:?} is not a key:value pair", token),
+        }
+    }
+}
+
+/// Everything wrong with one passport
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Check<'a> {
+    line: usize,
+    problems: Vec<Problem<'a>>,
+}
+
+impl Check<'_> {
+    /// Whether all the required fields are there, whatever their values
+    fn has_required_fields(&self) -> bool {
+        !self
+            .problems
+            .iter()
+            .any(|p| matches!(p, Problem::Missing(_)))
+    }
+
+    fn is_valid(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+/// Check a passport against the schema
+fn check<'a>(passport: &Passport<'a>, schema: &[FieldSpec]) -> Check<'a> {
+    let mut problems: Vec<Problem> = passport
+        .malformed
+        .iter()
+        .map(|&token| Problem::Malformed(token))
+        .collect();
+    for spec in schema {
+        let mut values = passport
+            .fields
+            .iter()
+            .filter(|(key, _)| *key == spec.key)
+            .map(|&(_, value)| value);
+        match values.next() {
+            None if spec.required => problems.push(Problem::Missing(spec.key)),
+            None => {}
+            Some(value) => {
+                if let Err(reason) = spec.rule.check(value) {
+                    problems.push(Problem::Invalid {
+                        key: spec.key,
+                        value,
+                        reason,
+                    });
+                }
+                if values.next().is_some() {
+                    problems.push(Problem::Repeated(spec.key));
+                }
+            }
+        }
+    }
+    problems.extend(
+        passport
+            .fields
+            .iter()
+            .filter(|(key, _)| !schema.iter().any(|spec| spec.key == *key))
+            .map(|&(key, _)| Problem::Unknown(key)),
+    );
+    Check {
+        line: passport.line,
+        problems,
+    }
+}
+
+fn check_all<'a>(passports: &[Passport<'a>]) -> Vec<Check<'a>> {
+    passports
+        .iter()
+        .map(|p| check(p, PASSPORT_SCHEMA))
+        .collect()
+}
+
+fn part1(checks: &[Check]) -> usize {
+    checks.iter().filter(|c| c.has_required_fields()).count()
+}
+
+fn part2(checks: &[Check]) -> usize {
+    checks.iter().filter(|c| c.is_valid()).count()
+}
+
+fn main() {
+    let setup_time = std::time::Instant::now();
+    let input_str =
+        std::fs::read_to_string("inputs/day04.txt").expect("Could not read day 4 input file");
+    let passports = parse_passports(&input_str);
+    let checks = check_all(&passports);
+    println!(
+        "Setup took {:.6} microseconds",
+        setup_time.elapsed().as_micros()
+    );
+
+    let part1_time = std::time::Instant::now();
+    let part1_solution = part1(&checks);
+    println!(
+        "Part 1 took {:.6} microseconds",
+        part1_time.elapsed().as_micros()
+    );
+
+    let part2_time = std::time::Instant::now();
+    let part2_solution = part2(&checks);
+    println!(
+        "Part 2 took {:.6} microseconds",
+        part2_time.elapsed().as_micros()
+    );
+
+    println!();
+    println!("First puzzle: {}", part1_solution);
+    println!("Second puzzle: {}", part2_solution);
+
+    // With `--explain`, say what is wrong with every passport that was turned away
+    if std::env::args().any(|a| a == "--explain") {
+        for check in checks.iter().filter(|c| !c.is_valid()) {
+            let problems: Vec<String> = check.problems.iter().map(|p| p.to_string()).collect();
+            println!("Line {}: {}", check.line, problems.join("; "));
+        }
+    }
+}
+
+#[test]
+fn test_part1() {
+    let input = "ecl:gry pid:860033327 eyr:2020 hcl:#fffffd
+byr:1937 iyr:2017 cid:147 hgt:183cm
+
+iyr:2013 ecl:amb cid:350 eyr:2023 pid:028048884
+hcl:#cfa07d byr:1929
+
+hcl:#ae17e1 iyr:2013
+eyr:2024
+ecl:brn pid:760753108 byr:1931
+hgt:179cm
+
+hcl:#cfa07d eyr:2025 pid:166559648
+iyr:2011 ecl:brn hgt:59in
+";
+    let checks = check_all(&parse_passports(input));
+
+    assert_eq!(part1(&checks), 2);
+    assert_eq!(checks[1].line, 4);
+    assert_eq!(checks[1].problems, vec![Problem::Missing("hgt")]);
+    assert_eq!(
+        checks[3].problems,
+        vec![Problem::Missing("byr")],
+        "cid is optional, so only byr is missing"
+    );
+}
+
+#[test]
+fn test_part2() {
+    let invalid = "eyr:1972 cid:100
+hcl:#18171d ecl:amb hgt:170 pid:186cm iyr:2018 byr:1926
+
+iyr:2019
+hcl:#602927 eyr:1967 hgt:170cm
+ecl:grn pid:012533040 byr:1946
+
+hcl:dab227 iyr:2012
+ecl:brn hgt:182cm pid:021572410 eyr:2020 byr:1992 cid:277
+
+hgt:59cm ecl:zzz
+eyr:2038 hcl:74454a iyr:2023
+pid:3556412378 byr:2007
+";
+    let checks = check_all(&parse_passports(invalid));
+    assert_eq!(part1(&checks), 4);
+    assert_eq!(part2(&checks), 0);
+
+    let valid = "pid:087499704 hgt:74in ecl:grn iyr:2012 eyr:2030 byr:1980
+hcl:#623a2f
+
+eyr:2029 ecl:blu cid:129 byr:1989
+iyr:2014 pid:896056539 hcl:#a97842 hgt:165cm
+
+hcl:#888785
+hgt:164cm byr:2001 iyr:2015 cid:88
+pid:545766238 ecl:hzl
+eyr:2022
+
+iyr:2010 hgt:158cm hcl:#b6652a ecl:blu byr:1944 eyr:2021 pid:093154719
+";
+    let checks = check_all(&parse_passports(valid));
+    assert_eq!(part2(&checks), 4);
+}
+
+#[test]
+fn test_rules() {
+    let rule = |key| {
+        &PASSPORT_SCHEMA
+            .iter()
+            .find(|spec| spec.key == key)
+            .expect("No such field")
+            .rule
+    };
+    assert!(rule("byr").check("2002").is_ok());
+    assert!(rule("byr").check("2003").is_err());
+    assert!(rule("byr").check("02002").is_err());
+    assert!(rule("hgt").check("60in").is_ok());
+    assert!(rule("hgt").check("190cm").is_ok());
+    assert!(rule("hgt").check("190in").is_err());
+    assert!(rule("hgt").check("190").is_err());
+    assert!(rule("hgt").check("cm").is_err());
+    assert!(rule("hcl").check("#123abc").is_ok());
+    assert!(rule("hcl").check("#123abz").is_err());
+    assert!(rule("hcl").check("123abc").is_err());
+    assert!(rule("ecl").check("brn").is_ok());
+    assert!(rule("ecl").check("wat").is_err());
+    assert!(rule("pid").check("000000001").is_ok());
+    assert!(rule("pid").check("0123456789").is_err());
+    assert!(rule("cid").check("").is_ok());
+}
+
+#[test]
+fn test_problems() {
+    let input = "\r\n\r\nbyr:2003 hgt:190in hcl:#123abc\r\n   \r\n\r\n";
+    let input = format!("{}ecl:brn byr:1980 ecl:blu xyz:1 junk\n", input);
+    let checks = check_all(&parse_passports(&input));
+    assert_eq!(checks.len(), 2);
+    assert_eq!(checks[0].line, 3);
+    assert_eq!(
+        checks[0].problems[0],
+        Problem::Invalid {
+            key: "byr",
+            value: "2003",
+            reason: "not between 1920 and 2002".to_string(),
+        }
+    );
+    let explained: Vec<String> = checks[0].problems.iter().map(|p| p.to_string()).collect();
+    assert_eq!(
+        explained[1..],
+        [
+            "iyr is missing",
+            "eyr is missing",
+            "hgt \"190in\" is not between 59in and 76in",
+            "ecl is missing",
+            "pid is missing",
+        ]
+    );
+    assert_eq!(checks[1].line, 6);
+    assert!(checks[1].problems.contains(&Problem::Repeated("ecl")));
+    assert!(checks[1].problems.contains(&Problem::Unknown("xyz")));
+    assert_eq!(checks[1].problems[0], Problem::Malformed("junk"));
+    assert!(!checks[1].has_required_fields());
+}
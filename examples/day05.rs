@@ -0,0 +1,216 @@
+/// How many rows of seats the plane has
+const ROWS: usize = 128;
+/// How many seats there are in each row
+const COLUMNS: usize = 8;
+
+/// A seat, numbered as `row * 8 + column`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct SeatId(u16);
+
+impl SeatId {
+    /// Decode a boarding pass like `FBFBBFFRLR`. Each letter is one bit of the seat ID,
+    /// with `F` and `L` for 0 and `B` and `R` for 1, so the first seven letters give the
+    /// row and the last three the column.
+    fn parse(pass: &str) -> Result<Self, String> {
+        let pass = pass.trim();
+        if pass.len() != 10 {
+            return Err(format!("{:?} is not 10 letters long", pass));
+        }
+        pass.chars()
+            .enumerate()
+            .try_fold(0, |id, (idx, c)| {
+                let bit = match (idx < 7, c) {
+                    (true, 'F') | (false, 'L') => 0,
+                    (true, 'B') | (false, 'R') => 1,
+                    (true, _) => return Err(format!("{:?} is not F or B", c)),
+                    (false, _) => return Err(format!("{:?} is not L or R", c)),
+                };
+                Ok(id << 1 | bit)
+            })
+            .map(SeatId)
+    }
+
+    fn row(self) -> usize {
+        self.0 as usize / COLUMNS
+    }
+
+    fn column(self) -> usize {
+        self.0 as usize % COLUMNS
+    }
+}
+
+/// Which seats on the plane are taken
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SeatMap {
+    taken: Vec<bool>,
+}
+
+impl SeatMap {
+    fn new(seats: &[SeatId]) -> Self {
+        let mut taken = vec![false; ROWS * COLUMNS];
+        for seat in seats {
+            taken[seat.0 as usize] = true;
+        }
+        SeatMap { taken }
+    }
+
+    fn is_taken(&self, seat: SeatId) -> bool {
+        self.taken[seat.0 as usize]
+    }
+
+    /// The free seats, in order
+    fn free_seats(&self) -> impl Iterator<Item = SeatId> + '_ {
+        (0..self.taken.len() as u16)
+            .map(SeatId)
+            .filter(move |&s| !self.is_taken(s))
+    }
+
+    /// The free seats with a taken seat on both sides of them by ID. The seats missing
+    /// from the very front and back of the plane don't count.
+    fn gaps(&self) -> impl Iterator<Item = SeatId> + '_ {
+        self.free_seats().filter(move |s| {
+            s.0 > 0
+                && (s.0 as usize) < self.taken.len() - 1
+                && self.is_taken(SeatId(s.0 - 1))
+                && self.is_taken(SeatId(s.0 + 1))
+        })
+    }
+
+    /// Draw the rows from the first to the last with a seat taken, with `#` for a taken
+    /// seat, `.` for a free one and an aisle down the middle
+    fn render(&self) -> String {
+        let rows_taken: Vec<usize> = (0..ROWS)
+            .filter(|&row| (0..COLUMNS).any(|col| self.taken[row * COLUMNS + col]))
+            .collect();
+        let (first, last) = match (rows_taken.first(), rows_taken.last()) {
+            (Some(&first), Some(&last)) => (first, last),
+            _ => return String::new(),
+        };
+        (first..=last)
+            .map(|row| {
+                let seat = |col: usize| {
+                    if self.taken[row * COLUMNS + col] {
+                        '#'
+                    } else {
+                        '.'
+                    }
+                };
+                format!(
+                    "{:>3} {} {}\n",
+                    row,
+                    (0..COLUMNS / 2).map(seat).collect::<String>(),
+                    (COLUMNS / 2..COLUMNS).map(seat).collect::<String>()
+                )
+            })
+            .collect()
+    }
+}
+
+fn part1(seats: &[SeatId]) -> Option<SeatId> {
+    seats.iter().copied().max()
+}
+
+/// Our seat is the only gap in the map
+fn part2(map: &SeatMap) -> Option<SeatId> {
+    let mut gaps = map.gaps();
+    match (gaps.next(), gaps.next()) {
+        (Some(seat), None) => Some(seat),
+        _ => None,
+    }
+}
+
+fn main() {
+    let setup_time = std::time::Instant::now();
+    let input_str =
+        std::fs::read_to_string("inputs/day05.txt").expect("Could not read day 5 input file");
+    let seats: Vec<SeatId> = input_str
+        .lines()
+        .enumerate()
+        .map(|(idx, l)| SeatId::parse(l).unwrap_or_else(|e| panic!("Line {}: {}", idx + 1, e)))
+        .collect();
+    let map = SeatMap::new(&seats);
+    println!(
+        "Setup took {:.6} microseconds",
+        setup_time.elapsed().as_micros()
+    );
+
+    let part1_time = std::time::Instant::now();
+    let part1_solution = part1(&seats).expect("There are no boarding passes");
+    println!(
+        "Part 1 took {:.6} microseconds",
+        part1_time.elapsed().as_micros()
+    );
+
+    let part2_time = std::time::Instant::now();
+    let part2_solution = part2(&map).expect("There is not exactly one free seat to take");
+    println!(
+        "Part 2 took {:.6} microseconds",
+        part2_time.elapsed().as_micros()
+    );
+
+    println!();
+    println!("First puzzle: {}", part1_solution.0);
+    println!(
+        "Second puzzle: {} (row {}, column {})",
+        part2_solution.0,
+        part2_solution.row(),
+        part2_solution.column()
+    );
+    println!("Free seats: {}", map.free_seats().count());
+
+    // With `--map`, draw the plane
+    if std::env::args().any(|a| a == "--map") {
+        print!("{}", map.render());
+    }
+}
+
+#[test]
+fn test_part1() {
+    let examples = [
+        ("FBFBBFFRLR", 44, 5, 357),
+        ("BFFFBBFRRR", 70, 7, 567),
+        ("FFFBBBFRRR", 14, 7, 119),
+        ("BBFFBBFRLL", 102, 4, 820),
+    ];
+    for (pass, row, column, id) in examples {
+        let seat = SeatId::parse(pass).expect("Could not decode boarding pass");
+        assert_eq!((seat.row(), seat.column(), seat.0), (row, column, id));
+    }
+
+    let seats: Vec<SeatId> = examples
+        .iter()
+        .map(|(pass, ..)| SeatId::parse(pass).expect("Could not decode boarding pass"))
+        .collect();
+    assert_eq!(part1(&seats), Some(SeatId(820)));
+}
+
+#[test]
+fn test_parse_errors() {
+    assert!(SeatId::parse("FBFBBFFRL").is_err());
+    assert!(SeatId::parse("FBFBBFLRLR").is_err());
+    assert!(SeatId::parse("FBFBBFFRLF").is_err());
+    assert_eq!(SeatId::parse("BBBBBBBRRR\r"), Ok(SeatId(1023)));
+}
+
+#[test]
+fn test_part2() {
+    let mut seats: Vec<SeatId> = (40..60).map(SeatId).collect();
+    seats.retain(|&s| s != SeatId(47));
+    let map = SeatMap::new(&seats);
+    assert_eq!(part2(&map), Some(SeatId(47)));
+    assert_eq!(map.free_seats().count(), ROWS * COLUMNS - 19);
+
+    // Two gaps, so no way to tell which is ours
+    seats.retain(|&s| s != SeatId(50));
+    assert_eq!(part2(&SeatMap::new(&seats)), None);
+}
+
+#[test]
+fn test_render() {
+    let seats: Vec<SeatId> = (10..20).filter(|&id| id != 13).map(SeatId).collect();
+    assert_eq!(
+        SeatMap::new(&seats).render(),
+        "  1 ..## #.##\n  2 #### ....\n"
+    );
+    assert_eq!(SeatMap::new(&[]).render(), "");
+}
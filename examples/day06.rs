@@ -1,36 +1,308 @@
-use std::collections::HashSet;
-
-use itertools::Itertools;
-
-fn part2(groups: &[&str]) -> usize {
-    groups
-        .iter()
-        .map(|&g| {
-            g.lines()
-                .map(|person| person.chars().collect::<HashSet<char>>())
-                .reduce(|acc, person| acc.intersection(&person).copied().collect::<HashSet<_>>())
-                .expect("Could not calculate intersection of sets")
-                .len()
+use rs_aoc::records::{records, Record};
+use std::collections::BTreeMap;
+
+/// The questions one or more people answered "yes" to, with bit 0 for question `a`, bit
+/// 1 for `b`, and so on up to `z`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+struct Answers(u32);
+
+impl Answers {
+    /// Every question from `a` to `z`
+    const ALL: Answers = Answers((1 << 26) - 1);
+
+    /// Read the answers on one person's line, like `abx`
+    fn parse(line: &str) -> Result<Self, String> {
+        line.trim()
+            .chars()
+            .try_fold(0, |mask, c| {
+                if c.is_ascii_lowercase() {
+                    Ok(mask | 1 << (c as u8 - b'a'))
+                } else {
+                    Err(format!("Answer {:?} is not one of the questions a to z", c))
+                }
+            })
+            .map(Answers)
+    }
+
+    /// How many questions there are
+    fn len(self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    fn contains(self, question: char) -> bool {
+        question.is_ascii_lowercase() && self.0 & 1 << (question as u8 - b'a') != 0
+    }
+
+    /// The questions, in alphabetical order
+    fn questions(self) -> impl Iterator<Item = char> {
+        ('a'..='z').filter(move |&q| self.contains(q))
+    }
+
+    fn union(self, other: Answers) -> Answers {
+        Answers(self.0 | other.0)
+    }
+
+    fn intersection(self, other: Answers) -> Answers {
+        Answers(self.0 & other.0)
+    }
+
+    fn symmetric_difference(self, other: Answers) -> Answers {
+        Answers(self.0 ^ other.0)
+    }
+}
+
+impl std::fmt::Display for Answers {
+    /// Write the questions as letters, like `abx`
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.questions().try_for_each(|q| write!(f, "{}", q))
+    }
+}
+
+/// What to ask of the answers in a group
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Query {
+    /// The questions anyone answered
+    Anyone,
+    /// The questions everyone answered
+    Everyone,
+    /// The questions an odd number of people answered
+    OddCount,
+    /// The questions at least this many people answered
+    AtLeast(usize),
+}
+
+/// The answers of each person in a group
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Group {
+    people: Vec<Answers>,
+}
+
+impl Group {
+    /// Read a group from its record, with one person per line
+    fn new(record: &Record<&str>) -> Result<Self, String> {
+        let people = record
+            .lines
+            .iter()
+            .enumerate()
+            .map(|(idx, line)| {
+                Answers::parse(line).map_err(|e| format!("Line {}: {}", record.line + idx, e))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Group { people })
+    }
+
+    fn union(&self) -> Answers {
+        self.people
+            .iter()
+            .fold(Answers::default(), |acc, &p| acc.union(p))
+    }
+
+    /// The questions everyone answered. A group with nobody in it answered nothing.
+    fn intersection(&self) -> Answers {
+        if self.people.is_empty() {
+            return Answers::default();
+        }
+        self.people
+            .iter()
+            .fold(Answers::ALL, |acc, &p| acc.intersection(p))
+    }
+
+    fn symmetric_difference(&self) -> Answers {
+        self.people
+            .iter()
+            .fold(Answers::default(), |acc, &p| acc.symmetric_difference(p))
+    }
+
+    /// The questions at least `k` people answered
+    fn at_least(&self, k: usize) -> Answers {
+        if k == 0 {
+            return Answers::ALL;
+        }
+        Answers(
+            (0..26)
+                .filter(|bit| self.people.iter().filter(|p| p.0 & 1 << bit != 0).count() >= k)
+                .fold(0, |mask, bit| mask | 1 << bit),
+        )
+    }
+
+    fn answer(&self, query: Query) -> Answers {
+        match query {
+            Query::Anyone => self.union(),
+            Query::Everyone => self.intersection(),
+            Query::OddCount => self.symmetric_difference(),
+            Query::AtLeast(k) => self.at_least(k),
+        }
+    }
+}
+
+/// Read the groups, which are separated by blank lines
+fn parse_groups(input: &str) -> Result<Vec<Group>, String> {
+    records(input).map(|r| Group::new(&r)).collect()
+}
+
+/// The answer to the query for each group, in order
+fn per_group(groups: &[Group], query: Query) -> Vec<Answers> {
+    groups.iter().map(|g| g.answer(query)).collect()
+}
+
+/// How many questions the query picks out, summed over all the groups
+fn total(groups: &[Group], query: Query) -> usize {
+    per_group(groups, query).iter().map(|a| a.len()).sum()
+}
+
+fn part1(groups: &[Group]) -> usize {
+    total(groups, Query::Anyone)
+}
+
+fn part2(groups: &[Group]) -> usize {
+    total(groups, Query::Everyone)
+}
+
+/// How one question was answered across all the groups
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct QuestionStats {
+    question: char,
+    /// Groups where anyone answered it
+    groups_anyone: usize,
+    /// Groups where everyone answered it
+    groups_everyone: usize,
+    /// People who answered it, in any group
+    people: usize,
+}
+
+/// A summary of the answers in every group
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Statistics {
+    /// One entry for each question, from `a` to `z`
+    questions: Vec<QuestionStats>,
+    /// How many groups there are of each size
+    group_sizes: BTreeMap<usize, usize>,
+    groups: usize,
+    people: usize,
+}
+
+fn statistics(groups: &[Group]) -> Statistics {
+    let questions = ('a'..='z')
+        .map(|question| QuestionStats {
+            question,
+            groups_anyone: groups
+                .iter()
+                .filter(|g| g.union().contains(question))
+                .count(),
+            groups_everyone: groups
+                .iter()
+                .filter(|g| g.intersection().contains(question))
+                .count(),
+            people: groups
+                .iter()
+                .flat_map(|g| &g.people)
+                .filter(|p| p.contains(question))
+                .count(),
+        })
+        .collect();
+    let mut group_sizes = BTreeMap::new();
+    for group in groups {
+        *group_sizes.entry(group.people.len()).or_insert(0) += 1;
+    }
+    Statistics {
+        questions,
+        group_sizes,
+        groups: groups.len(),
+        people: groups.iter().map(|g| g.people.len()).sum(),
+    }
+}
+
+impl Statistics {
+    /// The questions everyone answered in the most groups, in alphabetical order
+    fn most_universal(&self) -> Vec<char> {
+        self.universal_by(|counts| counts.max())
+    }
+
+    /// The questions everyone answered in the fewest groups, in alphabetical order
+    fn least_universal(&self) -> Vec<char> {
+        self.universal_by(|counts| counts.min())
+    }
+
+    fn universal_by(
+        &self,
+        pick: impl FnOnce(&mut dyn Iterator<Item = usize>) -> Option<usize>,
+    ) -> Vec<char> {
+        let target = pick(&mut self.questions.iter().map(|q| q.groups_everyone));
+        self.questions
+            .iter()
+            .filter(|q| Some(q.groups_everyone) == target)
+            .map(|q| q.question)
+            .collect()
+    }
+
+    /// Lay the statistics out as a plain text table
+    fn to_table(&self) -> String {
+        let mut table = String::from("question  groups (anyone)  groups (everyone)  people\n");
+        for q in &self.questions {
+            table += &format!(
+                "{:>8}  {:>15}  {:>17}  {:>6}\n",
+                q.question, q.groups_anyone, q.groups_everyone, q.people
+            );
+        }
+        table += &format!("\n{} groups of {} people\n", self.groups, self.people);
+        table += "group size  groups\n";
+        for (size, count) in &self.group_sizes {
+            table += &format!("{:>10}  {:>6}\n", size, count);
+        }
+        table += &format!(
+            "\nMost universally answered: {}\n",
+            self.most_universal().iter().collect::<String>()
+        );
+        table += &format!(
+            "Least universally answered: {}\n",
+            self.least_universal().iter().collect::<String>()
+        );
+        table
+    }
+
+    fn to_json(&self) -> String {
+        let questions: Vec<serde_json::Value> = self
+            .questions
+            .iter()
+            .map(|q| {
+                serde_json::json!({
+                    "question": q.question.to_string(),
+                    "groups_anyone": q.groups_anyone,
+                    "groups_everyone": q.groups_everyone,
+                    "people": q.people,
+                })
+            })
+            .collect();
+        let group_sizes: Vec<serde_json::Value> = self
+            .group_sizes
+            .iter()
+            .map(|(size, count)| serde_json::json!({ "size": size, "groups": count }))
+            .collect();
+        let letters = |qs: Vec<char>| qs.iter().map(char::to_string).collect::<Vec<_>>();
+        serde_json::json!({
+            "groups": self.groups,
+            "people": self.people,
+            "questions": questions,
+            "group_sizes": group_sizes,
+            "most_universal": letters(self.most_universal()),
+            "least_universal": letters(self.least_universal()),
         })
-        .sum()
+        .to_string()
+    }
 }
 
 fn main() {
     let setup_time = std::time::Instant::now();
     let input_str =
         std::fs::read_to_string("inputs/day06.txt").expect("Could not read day 6 input file");
-    let groups: Vec<&str> = input_str.as_str().split("\n\n").collect();
+    let groups: Vec<Group> = parse_groups(&input_str).unwrap_or_else(|e| panic!("{}", e));
     println!(
         "Setup took {:.6} microseconds",
         setup_time.elapsed().as_micros()
     );
 
     let part1_time = std::time::Instant::now();
-    let part1_solution: usize = groups
-        .iter()
-        .map(|&g| g.replace("\n", ""))
-        .map(|g| g.chars().unique().count())
-        .sum();
+    let part1_solution = part1(&groups);
     println!(
         "Part 1 took {:.6} microseconds",
         part1_time.elapsed().as_micros()
@@ -44,8 +316,34 @@ fn main() {
     );
 
     println!();
-    println!("First puzzle {:?}", part1_solution);
+    println!("First puzzle: {}", part1_solution);
     println!("Second puzzle: {}", part2_solution);
+    println!(
+        "Questions answered by an odd number of people: {}",
+        total(&groups, Query::OddCount)
+    );
+    println!(
+        "Questions answered by at least 3 people: {}",
+        total(&groups, Query::AtLeast(3))
+    );
+    if let Some(first) = groups.first() {
+        println!(
+            "In the first group, anyone answered {} and everyone answered {}",
+            first.answer(Query::Anyone),
+            first.answer(Query::Everyone)
+        );
+    }
+
+    // With `--stats table` or `--stats json`, report how each question was answered
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(idx) = args.iter().position(|a| a == "--stats") {
+        let stats = statistics(&groups);
+        match args.get(idx + 1).map(String::as_str) {
+            Some("table") => print!("{}", stats.to_table()),
+            Some("json") => println!("{}", stats.to_json()),
+            _ => panic!("--stats must be followed by table or json"),
+        }
+    }
 }
 
 #[test]
@@ -66,12 +364,9 @@ a
 
 b
 ";
-    let groups: Vec<&str> = input.split("\n\n").collect();
+    let groups: Vec<Group> = parse_groups(input).expect("Could not parse groups");
 
-    let part1_solution: usize = groups
-        .iter()
-        .map(|&g| g.replace("\n", "").chars().unique().count())
-        .sum();
+    let part1_solution: usize = part1(&groups);
 
     assert_eq!(part1_solution, 11);
 }
@@ -94,9 +389,167 @@ a
 
 b
 ";
-    let groups: Vec<&str> = input.split("\n\n").collect();
+    let groups: Vec<Group> = parse_groups(input).expect("Could not parse groups");
 
     let part2_solution: usize = part2(&groups);
 
     assert_eq!(part2_solution, 6);
 }
+
+#[test]
+fn test_answers() {
+    let answers = Answers::parse("bax").expect("Could not parse answers");
+    assert_eq!(answers, Answers(0b1000_0000_0000_0000_0000_0011));
+    assert_eq!(answers.len(), 3);
+    assert!(answers.contains('x'));
+    assert!(!answers.contains('c'));
+    assert!(!answers.contains('A'));
+    assert_eq!(answers.questions().collect::<String>(), "abx");
+    assert_eq!(answers.to_string(), "abx");
+
+    let other = Answers::parse("acx").expect("Could not parse answers");
+    assert_eq!(answers.union(other).questions().collect::<String>(), "abcx");
+    assert_eq!(
+        answers.intersection(other).questions().collect::<String>(),
+        "ax"
+    );
+    assert_eq!(
+        answers
+            .symmetric_difference(other)
+            .questions()
+            .collect::<String>(),
+        "bc"
+    );
+}
+
+#[test]
+fn test_group_queries() {
+    let record = Record {
+        line: 1,
+        lines: vec!["abc", "abd", "ae"],
+    };
+    let group = Group::new(&record).expect("Could not parse group");
+    let questions = |query| group.answer(query).questions().collect::<String>();
+    assert_eq!(questions(Query::Anyone), "abcde");
+    assert_eq!(questions(Query::Everyone), "a");
+    assert_eq!(questions(Query::OddCount), "acde");
+    assert_eq!(questions(Query::AtLeast(2)), "ab");
+    assert_eq!(questions(Query::AtLeast(3)), "a");
+    assert_eq!(questions(Query::AtLeast(4)), "");
+
+    let nobody = Group { people: Vec::new() };
+    assert_eq!(nobody.answer(Query::Everyone).len(), 0);
+    assert_eq!(nobody.answer(Query::Anyone).len(), 0);
+}
+
+#[test]
+fn test_per_group() {
+    let input = "abc
+
+a
+b
+c
+
+ab
+ac
+
+a
+a
+a
+a
+
+b
+";
+    let groups: Vec<Group> = parse_groups(input).expect("Could not parse groups");
+    let counts = |query| {
+        per_group(&groups, query)
+            .iter()
+            .map(|a| a.len())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(counts(Query::Anyone), vec![3, 3, 3, 1, 1]);
+    assert_eq!(counts(Query::Everyone), vec![3, 0, 1, 1, 1]);
+    assert_eq!(counts(Query::AtLeast(2)), vec![0, 0, 1, 1, 0]);
+}
+
+#[test]
+fn test_parse_groups_messy_input() {
+    // Windows line endings, several blank lines, and a blank line with spaces on it
+    let input = "abc\r\n\r\n\r\na\r\nb\r\n  \r\nab\r\nac\r\n\r\n";
+    let groups = parse_groups(input).expect("Could not parse groups");
+    assert_eq!(groups.len(), 3);
+    let counts = |query| {
+        per_group(&groups, query)
+            .iter()
+            .map(|a| a.len())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(counts(Query::Anyone), vec![3, 2, 3]);
+    assert_eq!(counts(Query::Everyone), vec![3, 0, 1]);
+}
+
+#[test]
+fn test_statistics() {
+    let input = "abc
+
+a
+b
+c
+
+ab
+ac
+
+a
+a
+a
+a
+
+b
+";
+    let stats = statistics(&parse_groups(input).expect("Could not parse groups"));
+    assert_eq!(stats.groups, 5);
+    assert_eq!(stats.people, 11);
+    assert_eq!(
+        stats.group_sizes,
+        [(1, 2), (2, 1), (3, 1), (4, 1)].into_iter().collect()
+    );
+    assert_eq!(
+        stats.questions[0],
+        QuestionStats {
+            question: 'a',
+            groups_anyone: 4,
+            groups_everyone: 3,
+            people: 8,
+        }
+    );
+    assert_eq!(stats.questions[25].people, 0);
+    assert_eq!(stats.most_universal(), vec!['a']);
+    assert_eq!(stats.least_universal().len(), 23);
+    assert!(!stats.least_universal().contains(&'b'));
+
+    let table = stats.to_table();
+    assert!(table.contains("       a                4                  3       8\n"));
+    assert!(table.contains("Most universally answered: a\n"));
+
+    let json: serde_json::Value =
+        serde_json::from_str(&stats.to_json()).expect("Statistics are not valid JSON");
+    assert_eq!(json["people"], 11);
+    assert_eq!(json["questions"][1]["groups_everyone"], 2);
+    assert_eq!(
+        json["group_sizes"][0],
+        serde_json::json!({ "size": 1, "groups": 2 })
+    );
+    assert_eq!(json["most_universal"], serde_json::json!(["a"]));
+}
+
+#[test]
+fn test_parse_errors() {
+    assert_eq!(
+        Answers::parse("abC"),
+        Err("Answer 'C' is not one of the questions a to z".to_string())
+    );
+    assert_eq!(
+        parse_groups("abc\n\nab\na1\n"),
+        Err("Line 4: Answer '1' is not one of the questions a to z".to_string())
+    );
+}
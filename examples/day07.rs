@@ -0,0 +1,368 @@
+use std::collections::{BTreeSet, HashMap};
+
+/// The colour of the bag the puzzle asks about
+const MY_BAG: &str = "shiny gold";
+
+/// The colour of a bag, and how many of each colour of bag it holds
+type Rule<'a> = (&'a str, Vec<(usize, &'a str)>);
+
+/// Read a rule like `light red bags contain 1 bright white bag, 2 muted yellow bags.`
+fn parse_rule(line: &str) -> Result<Rule<'_>, String> {
+    let (outer, contents) = line
+        .trim()
+        .trim_end_matches('.')
+        .split_once(" bags contain ")
+        .ok_or_else(|| "Expected \"<colour> bags contain ...\"".to_string())?;
+    if contents == "no other bags" {
+        return Ok((outer, Vec::new()));
+    }
+    let contents = contents
+        .split(", ")
+        .map(|item| {
+            let item = item
+                .strip_suffix(" bags")
+                .or_else(|| item.strip_suffix(" bag"))
+                .ok_or_else(|| format!("Expected {:?} to end in \"bag\" or \"bags\"", item))?;
+            let (count, colour) = item
+                .split_once(' ')
+                .ok_or_else(|| format!("Expected a count and a colour in {:?}", item))?;
+            let count = count
+                .parse()
+                .map_err(|_| format!("{:?} is not a count of bags", count))?;
+            Ok((count, colour))
+        })
+        .collect::<Result<_, String>>()?;
+    Ok((outer, contents))
+}
+
+/// Why a query on the rules couldn't be answered
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum BagError {
+    /// No rule mentions the colour
+    UnknownColour(String),
+    /// The bags hold each other in a loop, so there is no end to them. Starts and ends
+    /// with the same colour.
+    Cycle(Vec<String>),
+    /// Too many bags to count in a `usize`
+    Overflow(String),
+}
+
+impl std::fmt::Display for BagError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BagError::UnknownColour(colour) => write!(f, "No rule mentions {} bags", colour),
+            BagError::Cycle(path) => write!(f, "Bags contain each other: {}", path.join(" -> ")),
+            BagError::Overflow(colour) => write!(f, "{} bags hold too many bags to count", colour),
+        }
+    }
+}
+
+impl std::error::Error for BagError {}
+
+/// The rules as a graph with an edge from each bag to the bags it holds, weighted by how
+/// many it holds
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct BagGraph<'a> {
+    colours: Vec<&'a str>,
+    index: HashMap<&'a str, usize>,
+    /// For each colour, the colours inside it and how many of each
+    contents: Vec<Vec<(usize, usize)>>,
+    /// For each colour, the colours that hold it directly
+    containers: Vec<Vec<usize>>,
+}
+
+impl<'a> BagGraph<'a> {
+    fn parse(input: &'a str) -> Result<Self, String> {
+        let mut graph = BagGraph {
+            colours: Vec::new(),
+            index: HashMap::new(),
+            contents: Vec::new(),
+            containers: Vec::new(),
+        };
+        // The line each colour's rule is on, so a second rule for it can be rejected
+        let mut rule_lines: HashMap<&str, usize> = HashMap::new();
+        for (idx, line) in input.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let (outer, contents) =
+                parse_rule(line).map_err(|e| format!("Line {}: {}", idx + 1, e))?;
+            if let Some(first) = rule_lines.insert(outer, idx + 1) {
+                return Err(format!(
+                    "Line {}: {} bags already have a rule, on line {}",
+                    idx + 1,
+                    outer,
+                    first
+                ));
+            }
+            let outer = graph.node(outer);
+            for (count, colour) in contents {
+                let inner = graph.node(colour);
+                graph.contents[outer].push((inner, count));
+                graph.containers[inner].push(outer);
+            }
+        }
+        Ok(graph)
+    }
+
+    /// The index of the colour, adding it if it is new
+    fn node(&mut self, colour: &'a str) -> usize {
+        if let Some(&idx) = self.index.get(colour) {
+            return idx;
+        }
+        self.colours.push(colour);
+        self.contents.push(Vec::new());
+        self.containers.push(Vec::new());
+        self.index.insert(colour, self.colours.len() - 1);
+        self.colours.len() - 1
+    }
+
+    fn lookup(&self, colour: &str) -> Result<usize, BagError> {
+        self.index
+            .get(colour)
+            .copied()
+            .ok_or_else(|| BagError::UnknownColour(colour.to_string()))
+    }
+
+    /// Check that no bag ends up inside itself, so the rules make sense as a DAG.
+    /// Every query calls this before it answers.
+    fn validate(&self) -> Result<(), BagError> {
+        let mut done = vec![false; self.colours.len()];
+        let mut path = Vec::new();
+        (0..self.colours.len()).try_for_each(|bag| self.visit(bag, &mut done, &mut path))
+    }
+
+    /// Visit the bags inside `bag` depth first, looking for one that is already on
+    /// `path`, the bags we are inside of. `done` marks the bags with no loop below them.
+    fn visit(&self, bag: usize, done: &mut [bool], path: &mut Vec<usize>) -> Result<(), BagError> {
+        if done[bag] {
+            return Ok(());
+        }
+        if let Some(pos) = path.iter().position(|&b| b == bag) {
+            let cycle = path[pos..]
+                .iter()
+                .chain(std::iter::once(&bag))
+                .map(|&b| self.colours[b].to_string())
+                .collect();
+            return Err(BagError::Cycle(cycle));
+        }
+        path.push(bag);
+        for &(inner, _) in &self.contents[bag] {
+            self.visit(inner, done, path)?;
+        }
+        path.pop();
+        done[bag] = true;
+        Ok(())
+    }
+
+    /// Every colour of bag that could end up holding a bag of this colour, however
+    /// deep down
+    fn containers_of(&self, colour: &str) -> Result<BTreeSet<&'a str>, BagError> {
+        let start = self.lookup(colour)?;
+        self.validate()?;
+        let mut seen = vec![false; self.colours.len()];
+        let mut stack = vec![start];
+        while let Some(bag) = stack.pop() {
+            for &outer in &self.containers[bag] {
+                if !seen[outer] {
+                    seen[outer] = true;
+                    stack.push(outer);
+                }
+            }
+        }
+        Ok(seen
+            .iter()
+            .zip(&self.colours)
+            .filter(|(&seen, _)| seen)
+            .map(|(_, &colour)| colour)
+            .collect())
+    }
+
+    /// How many bags a bag of this colour holds, counting the bags inside those bags and
+    /// so on
+    fn count_inside(&self, colour: &str) -> Result<usize, BagError> {
+        let start = self.lookup(colour)?;
+        self.validate()?;
+        let mut memo = vec![None; self.colours.len()];
+        self.count_from(start, &mut memo)
+    }
+
+    /// Count the bags inside `bag`, remembering the totals for each colour in `memo`.
+    /// The graph must have been validated, so this can't go round in a loop.
+    fn count_from(&self, bag: usize, memo: &mut [Option<usize>]) -> Result<usize, BagError> {
+        if let Some(count) = memo[bag] {
+            return Ok(count);
+        }
+        let mut total: usize = 0;
+        for &(inner, count) in &self.contents[bag] {
+            let inside = self.count_from(inner, memo)?;
+            total = inside
+                .checked_add(1)
+                .and_then(|each| each.checked_mul(count))
+                .and_then(|these| total.checked_add(these))
+                .ok_or_else(|| BagError::Overflow(self.colours[bag].to_string()))?;
+        }
+        memo[bag] = Some(total);
+        Ok(total)
+    }
+
+    /// Write the graph in Graphviz's DOT language, with each edge labelled with how many
+    /// bags of that colour are held
+    fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph bags {\n");
+        for (idx, colour) in self.colours.iter().enumerate() {
+            dot += &format!("    n{} [label={:?}];\n", idx, colour);
+        }
+        for (outer, contents) in self.contents.iter().enumerate() {
+            for (inner, count) in contents {
+                dot += &format!("    n{} -> n{} [label=\"{}\"];\n", outer, inner, count);
+            }
+        }
+        dot += "}\n";
+        dot
+    }
+}
+
+fn part1(graph: &BagGraph) -> Result<usize, BagError> {
+    graph.containers_of(MY_BAG).map(|c| c.len())
+}
+
+fn part2(graph: &BagGraph) -> Result<usize, BagError> {
+    graph.count_inside(MY_BAG)
+}
+
+fn main() {
+    let setup_time = std::time::Instant::now();
+    let input_str =
+        std::fs::read_to_string("inputs/day07.txt").expect("Could not read day 7 input file");
+    let graph = BagGraph::parse(&input_str).unwrap_or_else(|e| panic!("{}", e));
+    println!(
+        "Setup took {:.6} microseconds",
+        setup_time.elapsed().as_micros()
+    );
+
+    let part1_time = std::time::Instant::now();
+    let part1_solution = part1(&graph).unwrap_or_else(|e| panic!("{}", e));
+    println!(
+        "Part 1 took {:.6} microseconds",
+        part1_time.elapsed().as_micros()
+    );
+
+    let part2_time = std::time::Instant::now();
+    let part2_solution = part2(&graph).unwrap_or_else(|e| panic!("{}", e));
+    println!(
+        "Part 2 took {:.6} microseconds",
+        part2_time.elapsed().as_micros()
+    );
+
+    println!();
+    println!("First puzzle: {}", part1_solution);
+    println!("Second puzzle: {}", part2_solution);
+
+    // With `--dot`, print the rules as a graph for Graphviz to draw
+    if std::env::args().any(|a| a == "--dot") {
+        print!("{}", graph.to_dot());
+    }
+}
+
+#[cfg(test)]
+const EXAMPLE: &str = "light red bags contain 1 bright white bag, 2 muted yellow bags.
+dark orange bags contain 3 bright white bags, 4 muted yellow bags.
+bright white bags contain 1 shiny gold bag.
+muted yellow bags contain 2 shiny gold bags, 9 faded blue bags.
+shiny gold bags contain 1 dark olive bag, 2 vibrant plum bags.
+dark olive bags contain 3 faded blue bags, 4 dotted black bags.
+vibrant plum bags contain 5 faded blue bags, 6 dotted black bags.
+faded blue bags contain no other bags.
+dotted black bags contain no other bags.
+";
+
+#[test]
+fn test_part1() {
+    let graph = BagGraph::parse(EXAMPLE).expect("Could not parse rules");
+    assert_eq!(part1(&graph), Ok(4));
+    assert_eq!(
+        graph
+            .containers_of(MY_BAG)
+            .map(|c| c.into_iter().collect::<Vec<_>>()),
+        Ok(vec![
+            "bright white",
+            "dark orange",
+            "light red",
+            "muted yellow"
+        ])
+    );
+    assert_eq!(graph.containers_of("light red").map(|c| c.len()), Ok(0));
+}
+
+#[test]
+fn test_part2() {
+    let graph = BagGraph::parse(EXAMPLE).expect("Could not parse rules");
+    assert_eq!(part2(&graph), Ok(32));
+
+    let input = "shiny gold bags contain 2 dark red bags.
+dark red bags contain 2 dark orange bags.
+dark orange bags contain 2 dark yellow bags.
+dark yellow bags contain 2 dark green bags.
+dark green bags contain 2 dark blue bags.
+dark blue bags contain 2 dark violet bags.
+dark violet bags contain no other bags.
+";
+    let graph = BagGraph::parse(input).expect("Could not parse rules");
+    assert_eq!(part2(&graph), Ok(126));
+}
+
+#[test]
+fn test_errors() {
+    let graph = BagGraph::parse(EXAMPLE).expect("Could not parse rules");
+    assert_eq!(
+        graph.count_inside("plaid"),
+        Err(BagError::UnknownColour("plaid".to_string()))
+    );
+
+    let input = "shiny gold bags contain 1 dark red bag.
+dark red bags contain 2 dark blue bags, 1 faded blue bag.
+dark blue bags contain 1 shiny gold bag.
+";
+    let graph = BagGraph::parse(input).expect("Could not parse rules");
+    let err = part2(&graph).expect_err("Bags inside themselves can't be counted");
+    assert_eq!(
+        err.to_string(),
+        "Bags contain each other: shiny gold -> dark red -> dark blue -> shiny gold"
+    );
+    assert_eq!(
+        graph.count_inside("faded blue"),
+        Err(err.clone()),
+        "The loop is caught whichever bag is asked about"
+    );
+    assert_eq!(part1(&graph), Err(err));
+
+    assert_eq!(
+        BagGraph::parse("ok bags contain no other bags.\nbad bags contain lots of bags."),
+        Err("Line 2: \"lots\" is not a count of bags".to_string())
+    );
+    assert_eq!(
+        BagGraph::parse(
+            "a bags contain 1 b bag.\nb bags contain no other bags.\na bags contain 1 b bag."
+        ),
+        Err("Line 3: a bags already have a rule, on line 1".to_string())
+    );
+    assert!(parse_rule("just some words").is_err());
+}
+
+#[test]
+fn test_to_dot() {
+    let graph = BagGraph::parse(
+        "bright white bags contain 1 shiny gold bag.\nshiny gold bags contain no other bags.",
+    )
+    .expect("Could not parse rules");
+    assert_eq!(
+        graph.to_dot(),
+        "digraph bags {
+    n0 [label=\"bright white\"];
+    n1 [label=\"shiny gold\"];
+    n0 -> n1 [label=\"1\"];
+}
+"
+    );
+}
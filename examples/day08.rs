@@ -238,7 +238,7 @@ acc +1
 jmp -4
 acc +6";
     let input: Vec<Rule> = input_str.lines().map(Rule::new).collect();
-    let input_mapped = (0_i64..).zip(input.into_iter()).collect::<HashMap<_, _>>();
+    let input_mapped = (0_i64..).zip(input).collect::<HashMap<_, _>>();
     let got = main_loop(&input_mapped);
     assert_eq!(got.accumulator, 5);
 }
@@ -255,7 +255,7 @@ acc +1
 jmp -4
 acc +6";
     let input: Vec<Rule> = input_str.lines().map(Rule::new).collect();
-    let input_mapped = (0_i64..).zip(input.into_iter()).collect::<HashMap<_, _>>();
+    let input_mapped = (0_i64..).zip(input).collect::<HashMap<_, _>>();
     let got = part2(&input_mapped);
     assert_eq!(got, 8);
 }
@@ -1,48 +1,141 @@
+use std::collections::BTreeSet;
+
 use counter::Counter;
+use itertools::Itertools;
 
-fn put_in_world(starting_ints: &mut Vec<usize>) {
-    let max = starting_ints
-        .iter()
-        .reduce(|a, b| a.max(b))
-        .expect("Could not find a maximum");
+/// The specification of the adapters, the outlet they start from, and the device they
+/// power.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct JoltageRules {
+    /// The joltage differences an adapter can take as input, below its own rating
+    allowed_gaps: BTreeSet<usize>,
+    /// The effective rating of the charging outlet
+    outlet: usize,
+    /// How much higher the device is rated than the highest adapter
+    device_offset: usize,
+}
+
+impl JoltageRules {
+    fn max_gap(&self) -> usize {
+        self.allowed_gaps.iter().copied().max().unwrap_or(0)
+    }
+
+    fn allows(&self, gap: usize) -> bool {
+        self.allowed_gaps.contains(&gap)
+    }
+}
+
+impl Default for JoltageRules {
+    /// The rules from the puzzle: gaps of 1 to 3 jolts, an outlet at 0, and a device 3
+    /// jolts above the highest adapter
+    fn default() -> Self {
+        JoltageRules {
+            allowed_gaps: (1..=3).collect(),
+            outlet: 0,
+            device_offset: 3,
+        }
+    }
+}
+
+/// Add the outlet and the device to the adapters and sort them into a chain. Adapters
+/// rated below the outlet can never be plugged in, so they are taken out of the chain and
+/// given back, in the order they were in.
+fn put_in_world(starting_ints: &mut Vec<usize>, rules: &JoltageRules) -> Vec<usize> {
+    let (chain, below_outlet) = starting_ints.iter().partition(|&&a| a >= rules.outlet);
+    *starting_ints = chain;
 
-    let to_add = vec![0, *max + 3];
-    starting_ints.extend(to_add.iter());
+    // The device is rated `device_offset` higher than the highest adapter. With no
+    // adapters at all, the device plugs straight into the outlet.
+    let max = starting_ints.iter().copied().max().unwrap_or(rules.outlet);
+
+    starting_ints.extend([rules.outlet, max + rules.device_offset]);
     starting_ints.sort_unstable();
+    below_outlet
+}
+
+/// A joltage difference in the chain that the rules do not allow
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct InvalidGap {
+    /// The index in the chain of the item below the gap
+    position: usize,
+    /// The ratings on either side of the gap
+    from: usize,
+    to: usize,
+}
+
+impl InvalidGap {
+    fn gap(&self) -> usize {
+        self.to - self.from
+    }
+}
+
+/// Every joltage difference in the chain that uses every adapter
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct GapHistogram {
+    /// How many times each difference occurs, including the invalid ones
+    counts: Counter<usize>,
+    /// Where the chain breaks the rules, in chain order
+    invalid_gaps: Vec<InvalidGap>,
+    /// The adapters left out of the chain because they are rated below the outlet
+    below_outlet: Vec<usize>,
+}
+
+impl GapHistogram {
+    fn count(&self, gap: usize) -> usize {
+        self.counts[&gap]
+    }
+
+    fn is_valid(&self) -> bool {
+        self.invalid_gaps.is_empty() && self.below_outlet.is_empty()
+    }
+
+    /// The number of 1-jolt differences multiplied by the number of 3-jolt differences,
+    /// or `None` if the chain is invalid or leaves any adapter out
+    fn one_three_product(&self) -> Option<usize> {
+        self.is_valid().then(|| self.count(1) * self.count(3))
+    }
 }
 
-fn part1(ints: &[usize]) -> usize {
-    ints.windows(2)
-        .map(|w| {
-            w.last().expect("Could not get last") - w.iter().next().expect("Could not get first")
+/// The gaps in the chain from `put_in_world`, along with the adapters it left out
+fn gap_histogram(ints: &[usize], below_outlet: Vec<usize>, rules: &JoltageRules) -> GapHistogram {
+    let counts = ints.windows(2).map(|w| w[1] - w[0]).collect();
+    let invalid_gaps = ints
+        .windows(2)
+        .enumerate()
+        .filter(|(_, w)| !rules.allows(w[1] - w[0]))
+        .map(|(position, w)| InvalidGap {
+            position,
+            from: w[0],
+            to: w[1],
         })
-        .collect::<Counter<_>>()
-        .iter()
-        .map(|(_, &val)| val)
-        .product()
+        .collect();
+    GapHistogram {
+        counts,
+        invalid_gaps,
+        below_outlet,
+    }
 }
 
-fn part2(ints: &[usize]) -> usize {
-    let max_idx = ints.len() - 1;
-    let mut different_paths: Vec<usize> = vec![0; max_idx];
+/// Count the distinct arrangements of adapters that connect the outlet to the device.
+///
+/// `ints` must be the sorted chain produced by `put_in_world`, so that the outlet is the
+/// first item and the device is the last. Adapters with the same rating are distinct
+/// adapters, but one can only plug into the other if the rules allow a gap of 0.
+fn part2(ints: &[usize], rules: &JoltageRules) -> usize {
+    let max_gap = rules.max_gap();
+
+    // different_paths[i] is the number of ways to get from the outlet to item i
+    let mut different_paths: Vec<usize> = vec![0; ints.len()];
     different_paths[0] = 1;
-    for i in 0..=max_idx {
-        for j in 1..=3 {
-            // If we're over the end, skip
-            if i + j >= max_idx {
-                continue;
-            }
-
-            // How big is the joltage gap from item i to item i+j
-            let joltage_diff = ints[i + j] - ints[i];
-
-            // If it's > 3, not reachable
-            // It it's <= 3, we an reach the item at i+j from i
-            // Add the number of ways to get to item i to the number at i+j
-            if joltage_diff <= 3 {
-                different_paths[i + j] += different_paths[i];
-            }
-        }
+    for i in 1..ints.len() {
+        // Walk back over every earlier item within `max_gap` jolts of item i. Because of
+        // duplicate ratings, there can be more of them than there are allowed gaps.
+        different_paths[i] = (0..i)
+            .rev()
+            .take_while(|&j| ints[i] - ints[j] <= max_gap)
+            .filter(|&j| rules.allows(ints[i] - ints[j]))
+            .map(|j| different_paths[j])
+            .sum();
     }
     *different_paths
         .last()
@@ -50,33 +143,55 @@ fn part2(ints: &[usize]) -> usize {
 }
 
 fn main() {
+    let rules = JoltageRules::default();
+
     let setup_time = std::time::Instant::now();
     let mut ints: Vec<usize> = std::fs::read_to_string("inputs/day10.txt")
         .expect("Could not read day 10 input")
         .lines()
         .map(|l| l.parse().expect("Could not parse to usize"))
         .collect();
-    put_in_world(&mut ints);
+    let below_outlet = put_in_world(&mut ints, &rules);
     println!(
         "Setup took {:.6} microseconds",
         setup_time.elapsed().as_micros()
     );
 
     let part1_time = std::time::Instant::now();
-    let part1_soln = part1(&ints);
+    let histogram = gap_histogram(&ints, below_outlet, &rules);
+    let part1_soln = histogram.one_three_product().unwrap_or_else(|| {
+        panic!(
+            "Could not chain every adapter, bad gaps: [{}], below the outlet: {:?}",
+            histogram
+                .invalid_gaps
+                .iter()
+                .map(|g| format!("{} at position {}", g.gap(), g.position))
+                .join(", "),
+            histogram.below_outlet
+        )
+    });
     println!(
         "Part 1 took {:.6} microseconds",
         part1_time.elapsed().as_micros()
     );
 
     let part2_time = std::time::Instant::now();
-    let part2_soln = part2(&ints);
+    let part2_soln = part2(&ints, &rules);
     println!(
         "Part 2 took {:.6} microseconds",
         part2_time.elapsed().as_micros()
     );
 
     println!();
+    println!(
+        "Joltage differences: {}",
+        histogram
+            .counts
+            .iter()
+            .sorted()
+            .map(|(gap, count)| format!("{}: {}", gap, count))
+            .join(", ")
+    );
     println!("First Puzzle: {:?}", part1_soln);
     println!("Second Puzzle: {:?}", part2_soln);
 }
@@ -84,10 +199,10 @@ fn main() {
 #[test]
 fn test_part1_small() {
     let mut ints: Vec<usize> = vec![16, 10, 15, 5, 1, 11, 7, 19, 6, 12, 4];
-    put_in_world(&mut ints);
+    let below_outlet = put_in_world(&mut ints, &JoltageRules::default());
 
-    let got = part1(&ints);
-    let expected: usize = 35;
+    let got = gap_histogram(&ints, below_outlet, &JoltageRules::default()).one_three_product();
+    let expected = Some(35);
     assert_eq!(got, expected);
 }
 
@@ -97,19 +212,19 @@ fn test_part1_big() {
         28, 33, 18, 42, 31, 14, 46, 20, 48, 47, 24, 23, 49, 45, 19, 38, 39, 11, 1, 32, 25, 35, 8,
         17, 7, 9, 4, 2, 34, 10, 3,
     ];
-    put_in_world(&mut ints);
+    let below_outlet = put_in_world(&mut ints, &JoltageRules::default());
 
-    let got = part1(&ints);
-    let expected: usize = 220;
+    let got = gap_histogram(&ints, below_outlet, &JoltageRules::default()).one_three_product();
+    let expected = Some(220);
     assert_eq!(got, expected);
 }
 
 #[test]
 fn test_part2_small() {
     let mut ints: Vec<usize> = vec![16, 10, 15, 5, 1, 11, 7, 19, 6, 12, 4];
-    put_in_world(&mut ints);
+    put_in_world(&mut ints, &JoltageRules::default());
 
-    let got = part2(&ints);
+    let got = part2(&ints, &JoltageRules::default());
     let expected: usize = 8;
     assert_eq!(got, expected);
 }
@@ -120,9 +235,187 @@ fn test_part2_big() {
         28, 33, 18, 42, 31, 14, 46, 20, 48, 47, 24, 23, 49, 45, 19, 38, 39, 11, 1, 32, 25, 35, 8,
         17, 7, 9, 4, 2, 34, 10, 3,
     ];
-    put_in_world(&mut ints);
+    put_in_world(&mut ints, &JoltageRules::default());
 
-    let got = part2(&ints);
+    let got = part2(&ints, &JoltageRules::default());
     let expected: usize = 19208;
     assert_eq!(got, expected);
 }
+
+#[cfg(test)]
+fn brute_force_arrangements(adapters: &[usize], rules: &JoltageRules) -> usize {
+    // Try every subset of the adapters, and count the ones that form a valid chain
+    let adapters: Vec<usize> = adapters
+        .iter()
+        .copied()
+        .filter(|&a| a >= rules.outlet)
+        .collect();
+    let device = adapters.iter().copied().max().unwrap_or(rules.outlet) + rules.device_offset;
+    (0..1_usize << adapters.len())
+        .filter(|mask| {
+            let mut chain: Vec<usize> = (0..adapters.len())
+                .filter(|i| mask & (1 << i) != 0)
+                .map(|i| adapters[i])
+                .collect();
+            chain.extend([rules.outlet, device]);
+            chain.sort_unstable();
+            chain.windows(2).all(|w| rules.allows(w[1] - w[0]))
+        })
+        .count()
+}
+
+#[test]
+fn test_part2_empty() {
+    let mut ints: Vec<usize> = vec![];
+    put_in_world(&mut ints, &JoltageRules::default());
+    assert_eq!(ints, vec![0, 3]);
+    assert_eq!(part2(&ints, &JoltageRules::default()), 1);
+}
+
+#[test]
+fn test_part2_single() {
+    let mut ints: Vec<usize> = vec![2];
+    put_in_world(&mut ints, &JoltageRules::default());
+    assert_eq!(part2(&ints, &JoltageRules::default()), 1);
+
+    let mut ints: Vec<usize> = vec![4];
+    put_in_world(&mut ints, &JoltageRules::default());
+    assert_eq!(part2(&ints, &JoltageRules::default()), 0);
+}
+
+#[test]
+fn test_part2_duplicates() {
+    // Either of the two 1s can be used, but never both in the same chain
+    let mut ints: Vec<usize> = vec![1, 1, 2];
+    put_in_world(&mut ints, &JoltageRules::default());
+    assert_eq!(part2(&ints, &JoltageRules::default()), 3);
+
+    // All four 1s are within reach of the 2
+    let mut ints: Vec<usize> = vec![1, 1, 1, 1, 2];
+    put_in_world(&mut ints, &JoltageRules::default());
+    assert_eq!(part2(&ints, &JoltageRules::default()), 5);
+}
+
+proptest::proptest! {
+    #[test]
+    fn test_part2_matches_brute_force(
+        adapters in proptest::collection::vec(1_usize..=12, 0..=10)
+    ) {
+        let rules = JoltageRules::default();
+        let mut ints = adapters.clone();
+        put_in_world(&mut ints, &rules);
+        proptest::prop_assert_eq!(part2(&ints, &rules), brute_force_arrangements(&adapters, &rules));
+    }
+
+    #[test]
+    fn test_part2_custom_rules_match_brute_force(
+        adapters in proptest::collection::vec(0_usize..=15, 0..=10),
+        allowed_gaps in proptest::collection::btree_set(0_usize..=5, 0..=3),
+        outlet in 0_usize..=3,
+        device_offset in 1_usize..=5,
+    ) {
+        let rules = JoltageRules { allowed_gaps, outlet, device_offset };
+        let mut ints = adapters.clone();
+        put_in_world(&mut ints, &rules);
+        proptest::prop_assert_eq!(part2(&ints, &rules), brute_force_arrangements(&adapters, &rules));
+    }
+}
+
+#[test]
+fn test_custom_rules() {
+    // Only gaps of 1 or 2, from an outlet at 1 to a device 2 above the highest adapter
+    let rules = JoltageRules {
+        allowed_gaps: [1, 2].into_iter().collect(),
+        outlet: 1,
+        device_offset: 2,
+    };
+    let mut ints: Vec<usize> = vec![2, 3, 5];
+    let below_outlet = put_in_world(&mut ints, &rules);
+    assert_eq!(ints, vec![1, 2, 3, 5, 7]);
+
+    // Gaps of 1, 1, 2 and 2
+    let histogram = gap_histogram(&ints, below_outlet, &rules);
+    assert_eq!(histogram.count(1), 2);
+    assert_eq!(histogram.count(2), 2);
+    assert_eq!(histogram.one_three_product(), Some(0));
+
+    // 1-2-3-5-7 and 1-3-5-7
+    assert_eq!(part2(&ints, &rules), 2);
+
+    // A gap of 3 from the outlet can't be bridged
+    let mut ints: Vec<usize> = vec![4];
+    let below_outlet = put_in_world(&mut ints, &rules);
+    assert_eq!(
+        gap_histogram(&ints, below_outlet, &rules).one_three_product(),
+        None
+    );
+    assert_eq!(part2(&ints, &rules), 0);
+}
+
+#[test]
+fn test_gap_histogram() {
+    let mut ints: Vec<usize> = vec![16, 10, 15, 5, 1, 11, 7, 19, 6, 12, 4];
+    let below_outlet = put_in_world(&mut ints, &JoltageRules::default());
+
+    let histogram = gap_histogram(&ints, below_outlet, &JoltageRules::default());
+    assert!(histogram.is_valid());
+    assert_eq!(histogram.count(1), 7);
+    assert_eq!(histogram.count(2), 0);
+    assert_eq!(histogram.count(3), 5);
+    assert_eq!(histogram.one_three_product(), Some(35));
+}
+
+#[test]
+fn test_gap_histogram_invalid() {
+    // A gap of 5 between 2 and 7, and a gap of 0 between the two 8s
+    let mut ints: Vec<usize> = vec![1, 2, 7, 8, 8];
+    let below_outlet = put_in_world(&mut ints, &JoltageRules::default());
+    assert_eq!(ints, vec![0, 1, 2, 7, 8, 8, 11]);
+
+    let histogram = gap_histogram(&ints, below_outlet, &JoltageRules::default());
+    assert!(!histogram.is_valid());
+    assert_eq!(histogram.count(0), 1);
+    assert_eq!(histogram.count(1), 3);
+    assert_eq!(histogram.count(3), 1);
+    assert_eq!(histogram.count(5), 1);
+    assert_eq!(
+        histogram.invalid_gaps,
+        vec![
+            InvalidGap {
+                position: 2,
+                from: 2,
+                to: 7
+            },
+            InvalidGap {
+                position: 4,
+                from: 8,
+                to: 8
+            },
+        ]
+    );
+    assert_eq!(histogram.invalid_gaps[0].gap(), 5);
+    assert_eq!(histogram.one_three_product(), None);
+}
+
+#[test]
+fn test_below_outlet() {
+    // The 0 and the 1 can't plug into an outlet at 2, so the chain leaves them out
+    let rules = JoltageRules {
+        outlet: 2,
+        ..JoltageRules::default()
+    };
+    let mut ints: Vec<usize> = vec![3, 1, 5, 0];
+    let below_outlet = put_in_world(&mut ints, &rules);
+    assert_eq!(below_outlet, vec![1, 0]);
+    assert_eq!(ints, vec![2, 3, 5, 8]);
+
+    // The gaps are all fine, but part 1 has to use every adapter
+    let histogram = gap_histogram(&ints, below_outlet, &rules);
+    assert!(histogram.invalid_gaps.is_empty());
+    assert_eq!(histogram.below_outlet, vec![1, 0]);
+    assert!(!histogram.is_valid());
+    assert_eq!(histogram.one_three_product(), None);
+
+    // Part 2 only counts chains from the outlet, which can't include them anyway
+    assert_eq!(part2(&ints, &rules), 2);
+}
@@ -1,5 +1,6 @@
 use std::collections::{HashMap, HashSet};
 
+#[cfg(test)]
 use itertools::Itertools;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -58,14 +59,17 @@ fn count_adjacent_occupied(loc: (i32, i32), grid: &HashMap<(i32, i32), Seat>) ->
         .count()
 }
 
+/// Counts the occupied seats relevant to the seat at a location
+type AdjacencyFn = fn((i32, i32), &HashMap<(i32, i32), Seat>) -> usize;
+
 fn seat_iter(
     layout: &HashMap<(i32, i32), Seat>,
-    adjacency_fn: fn((i32, i32), &HashMap<(i32, i32), Seat>) -> usize,
+    adjacency_fn: AdjacencyFn,
     tolerance: usize,
 ) -> (HashMap<(i32, i32), Seat>, usize) {
     let occupied_adjacent_count: HashMap<(i32, i32), usize> = layout
         .iter()
-        .map(|(&loc, _)| (loc, adjacency_fn(loc, &layout)))
+        .map(|(&loc, _)| (loc, adjacency_fn(loc, layout)))
         .collect();
 
     // At empty spots, check for 0 occupied seats adjacent to it
@@ -98,14 +102,10 @@ fn seat_iter(
 
     let nchanges: usize = all_empty_adjacents.len() + four_or_more.len();
 
-    return (new_layout, nchanges);
+    (new_layout, nchanges)
 }
 
-fn solve(
-    layout: &HashMap<(i32, i32), Seat>,
-    adjacency_fn: fn((i32, i32), &HashMap<(i32, i32), Seat>) -> usize,
-    tolerance: usize,
-) -> usize {
+fn solve(layout: &HashMap<(i32, i32), Seat>, adjacency_fn: AdjacencyFn, tolerance: usize) -> usize {
     let mut n_changes: usize = 1;
     let mut changing_layout = layout.clone();
     while n_changes > 0 {
@@ -145,6 +145,9 @@ fn main() {
         "Part 1 took {:.6} microseconds",
         part1_time.elapsed().as_micros()
     );
+
+    println!();
+    println!("First Puzzle: {:?}", part1_soln);
 }
 
 #[test]
@@ -289,7 +292,7 @@ L.LLLLL.LL
         }
     }
 
-    let got = parse_input(&input);
+    let got = parse_input(input);
 
     assert_eq!(got, expected);
 }
@@ -363,8 +366,8 @@ L.#.L..#..
 
     // Iterate from the first for the length of `layouts`
     let mut got = vec![layouts[0].clone()];
-    for idx in 0..layouts.len() {
-        let new_result = seat_iter(&layouts[idx], count_adjacent_occupied, 4);
+    for layout in &layouts {
+        let new_result = seat_iter(layout, count_adjacent_occupied, 4);
         let new_layout = new_result.0;
         got.push(new_layout);
     }
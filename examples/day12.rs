@@ -0,0 +1,154 @@
+use rs_aoc::vec2::{Direction, Vec2};
+
+/// One of the navigation instructions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Action {
+    /// `N`, `S`, `E` or `W`: move towards a compass point
+    Move(Direction, i64),
+    /// `L` or `R`: turn clockwise by this many quarter turns, or anticlockwise if negative
+    Turn(i32),
+    /// `F`: move forwards
+    Forward(i64),
+}
+
+impl Action {
+    /// Read an instruction like `F10` or `R90`
+    fn parse(line: &str) -> Result<Self, String> {
+        let line = line.trim();
+        let mut chars = line.chars();
+        let letter = chars
+            .next()
+            .ok_or_else(|| "Expected an instruction".to_string())?;
+        let value: i64 = chars
+            .as_str()
+            .parse()
+            .map_err(|_| format!("{:?} is not a number", chars.as_str()))?;
+        let quarter_turns = || {
+            if value % 90 != 0 {
+                return Err(format!("Can only turn by multiples of 90°, not {}°", value));
+            }
+            i32::try_from(value / 90).map_err(|_| format!("{}° is too far to turn", value))
+        };
+        Ok(match letter {
+            'N' => Action::Move(Direction::North, value),
+            'S' => Action::Move(Direction::South, value),
+            'E' => Action::Move(Direction::East, value),
+            'W' => Action::Move(Direction::West, value),
+            'L' => Action::Turn(-quarter_turns()?),
+            'R' => Action::Turn(quarter_turns()?),
+            'F' => Action::Forward(value),
+            _ => return Err(format!("{:?} is not one of N, S, E, W, L, R, F", letter)),
+        })
+    }
+}
+
+/// Where the ship ends up when the instructions move it directly. It starts facing east.
+fn sail(actions: &[Action]) -> Vec2 {
+    let mut position = Vec2::ZERO;
+    let mut heading = Direction::East;
+    for action in actions {
+        match *action {
+            Action::Move(direction, distance) => position += direction.step() * distance,
+            Action::Turn(quarter_turns) => heading = heading.turn_right(quarter_turns),
+            Action::Forward(distance) => position += heading.step() * distance,
+        }
+    }
+    position
+}
+
+/// Where the ship ends up when the instructions move a waypoint, which is relative to the
+/// ship and turns around it, and the ship moves towards the waypoint
+fn sail_by_waypoint(actions: &[Action], waypoint: Vec2) -> Vec2 {
+    let mut position = Vec2::ZERO;
+    let mut waypoint = waypoint;
+    for action in actions {
+        match *action {
+            Action::Move(direction, distance) => waypoint += direction.step() * distance,
+            Action::Turn(quarter_turns) => waypoint = waypoint.rotate_right(quarter_turns),
+            Action::Forward(times) => position += waypoint * times,
+        }
+    }
+    position
+}
+
+fn part1(actions: &[Action]) -> i64 {
+    sail(actions).manhattan()
+}
+
+fn part2(actions: &[Action]) -> i64 {
+    sail_by_waypoint(actions, Vec2::new(10, 1)).manhattan()
+}
+
+fn main() {
+    let setup_time = std::time::Instant::now();
+    let input_str =
+        std::fs::read_to_string("inputs/day12.txt").expect("Could not read day 12 input file");
+    let actions: Vec<Action> = input_str
+        .lines()
+        .enumerate()
+        .map(|(idx, l)| Action::parse(l).unwrap_or_else(|e| panic!("Line {}: {}", idx + 1, e)))
+        .collect();
+    println!(
+        "Setup took {:.6} microseconds",
+        setup_time.elapsed().as_micros()
+    );
+
+    let part1_time = std::time::Instant::now();
+    let part1_solution = part1(&actions);
+    println!(
+        "Part 1 took {:.6} microseconds",
+        part1_time.elapsed().as_micros()
+    );
+
+    let part2_time = std::time::Instant::now();
+    let part2_solution = part2(&actions);
+    println!(
+        "Part 2 took {:.6} microseconds",
+        part2_time.elapsed().as_micros()
+    );
+
+    println!();
+    println!("First puzzle: {}", part1_solution);
+    println!("Second puzzle: {}", part2_solution);
+}
+
+#[cfg(test)]
+const EXAMPLE: &str = "F10
+N3
+F7
+R90
+F11
+";
+
+#[test]
+fn test_part1() {
+    let actions: Vec<Action> = EXAMPLE
+        .lines()
+        .map(|l| Action::parse(l).expect("Could not parse action"))
+        .collect();
+    assert_eq!(sail(&actions), Vec2::new(17, -8));
+    assert_eq!(part1(&actions), 25);
+}
+
+#[test]
+fn test_part2() {
+    let actions: Vec<Action> = EXAMPLE
+        .lines()
+        .map(|l| Action::parse(l).expect("Could not parse action"))
+        .collect();
+    assert_eq!(
+        sail_by_waypoint(&actions, Vec2::new(10, 1)),
+        Vec2::new(214, -72)
+    );
+    assert_eq!(part2(&actions), 286);
+}
+
+#[test]
+fn test_parse() {
+    assert_eq!(Action::parse("L270"), Ok(Action::Turn(-3)));
+    assert_eq!(Action::parse("W5\r"), Ok(Action::Move(Direction::West, 5)));
+    assert!(Action::parse("R45").is_err());
+    assert!(Action::parse("X1").is_err());
+    assert!(Action::parse("F").is_err());
+    assert!(Action::parse("").is_err());
+}
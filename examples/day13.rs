@@ -0,0 +1,126 @@
+use rs_aoc::modular::{solve_crt, Congruence, CrtError};
+
+/// The notes: when we can leave, and the buses in service at their places in the list
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Notes {
+    earliest: i128,
+    /// Each bus's place in the list and its ID, which is also how often it leaves
+    buses: Vec<(usize, i128)>,
+}
+
+impl Notes {
+    fn parse(input: &str) -> Result<Self, String> {
+        let mut lines = input.lines();
+        let earliest = lines
+            .next()
+            .ok_or_else(|| "Expected the earliest departure time".to_string())?;
+        let earliest = earliest
+            .trim()
+            .parse()
+            .map_err(|_| format!("{:?} is not a time", earliest))?;
+        let buses = lines
+            .next()
+            .ok_or_else(|| "Expected a list of buses".to_string())?
+            .trim()
+            .split(',')
+            .enumerate()
+            .filter(|&(_, bus)| bus != "x")
+            .map(|(idx, bus)| match bus.parse() {
+                Ok(id) if id > 0 => Ok((idx, id)),
+                _ => Err(format!("{:?} is not a bus ID or x", bus)),
+            })
+            .collect::<Result<_, String>>()?;
+        Ok(Notes { earliest, buses })
+    }
+}
+
+/// The first bus to leave at or after the earliest time, and how long it is to wait for it
+fn earliest_bus(notes: &Notes) -> Option<(i128, i128)> {
+    notes
+        .buses
+        .iter()
+        .map(|&(_, id)| (id, (-notes.earliest).rem_euclid(id)))
+        .min_by_key(|&(_, wait)| wait)
+}
+
+/// The times when each bus leaves as many minutes after it as its place in the list
+fn contest_times(notes: &Notes) -> Result<Congruence, CrtError> {
+    solve_crt(notes.buses.iter().map(|&(idx, id)| (-(idx as i128), id)))
+}
+
+fn part1(notes: &Notes) -> Option<i128> {
+    earliest_bus(notes).map(|(id, wait)| id * wait)
+}
+
+fn part2(notes: &Notes) -> Result<i128, CrtError> {
+    contest_times(notes).map(|times| times.residue)
+}
+
+fn main() {
+    let setup_time = std::time::Instant::now();
+    let input_str =
+        std::fs::read_to_string("inputs/day13.txt").expect("Could not read day 13 input file");
+    let notes = Notes::parse(&input_str).unwrap_or_else(|e| panic!("{}", e));
+    println!(
+        "Setup took {:.6} microseconds",
+        setup_time.elapsed().as_micros()
+    );
+
+    let part1_time = std::time::Instant::now();
+    let part1_solution = part1(&notes).expect("There are no buses");
+    println!(
+        "Part 1 took {:.6} microseconds",
+        part1_time.elapsed().as_micros()
+    );
+
+    let part2_time = std::time::Instant::now();
+    let part2_solution = part2(&notes).unwrap_or_else(|e| panic!("{}", e));
+    println!(
+        "Part 2 took {:.6} microseconds",
+        part2_time.elapsed().as_micros()
+    );
+
+    println!();
+    println!("First puzzle: {}", part1_solution);
+    println!("Second puzzle: {}", part2_solution);
+    if let Ok(times) = contest_times(&notes) {
+        println!("The buses line up again every {} minutes", times.modulus);
+    }
+}
+
+#[test]
+fn test_part1() {
+    let notes = Notes::parse("939\n7,13,x,x,59,x,31,19\n").expect("Could not parse notes");
+    assert_eq!(earliest_bus(&notes), Some((59, 5)));
+    assert_eq!(part1(&notes), Some(295));
+}
+
+#[test]
+fn test_part2() {
+    let examples = [
+        ("7,13,x,x,59,x,31,19", 1068781),
+        ("17,x,13,19", 3417),
+        ("67,7,59,61", 754018),
+        ("67,x,7,59,61", 779210),
+        ("67,7,x,59,61", 1261476),
+        ("1789,37,47,1889", 1202161486),
+    ];
+    for (buses, time) in examples {
+        let notes = Notes::parse(&format!("0\n{}\n", buses)).expect("Could not parse notes");
+        assert_eq!(part2(&notes), Ok(time), "for {}", buses);
+    }
+}
+
+#[test]
+fn test_shared_factors() {
+    // Buses 4 and 6 both leave at even times, so they can't be one minute apart
+    let notes = Notes::parse("0\n4,6\n").expect("Could not parse notes");
+    assert!(matches!(part2(&notes), Err(CrtError::NoSolution(..))));
+
+    // But two minutes apart is fine: 4 at 4, then 6 at 6, and again every 12 minutes
+    let notes = Notes::parse("0\n4,x,6\n").expect("Could not parse notes");
+    assert_eq!(contest_times(&notes), Ok(Congruence::new(4, 12)));
+
+    assert!(Notes::parse("0\n4,0\n").is_err());
+    assert!(Notes::parse("0\n").is_err());
+}
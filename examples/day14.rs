@@ -0,0 +1,324 @@
+/// How many bits the masks, values and addresses have
+const WORD_BITS: usize = 36;
+
+/// A mask like `XXXXXXXXXXXXXXXXXXXXXXXXXXXXX1XXXX0X`, split into the bits that are 1, 0
+/// and X
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+struct Mask {
+    ones: u64,
+    zeros: u64,
+    floating: u64,
+}
+
+impl Mask {
+    fn parse(mask: &str) -> Result<Self, String> {
+        if mask.len() != WORD_BITS {
+            return Err(format!("{:?} is not {} bits long", mask, WORD_BITS));
+        }
+        mask.chars().try_fold(Mask::default(), |m, c| {
+            let m = Mask {
+                ones: m.ones << 1,
+                zeros: m.zeros << 1,
+                floating: m.floating << 1,
+            };
+            match c {
+                '1' => Ok(Mask {
+                    ones: m.ones | 1,
+                    ..m
+                }),
+                '0' => Ok(Mask {
+                    zeros: m.zeros | 1,
+                    ..m
+                }),
+                'X' => Ok(Mask {
+                    floating: m.floating | 1,
+                    ..m
+                }),
+                _ => Err(format!("{:?} is not 0, 1 or X", c)),
+            }
+        })
+    }
+}
+
+/// One line of the initialization program
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Instruction {
+    /// `mask = ...`
+    SetMask(Mask),
+    /// `mem[address] = value`
+    Write { address: u64, value: u64 },
+}
+
+impl Instruction {
+    fn parse(line: &str) -> Result<Self, String> {
+        let (target, value) = line
+            .trim()
+            .split_once(" = ")
+            .ok_or_else(|| "Expected \"<target> = <value>\"".to_string())?;
+        if target == "mask" {
+            return Mask::parse(value).map(Instruction::SetMask);
+        }
+        let address = target
+            .strip_prefix("mem[")
+            .and_then(|t| t.strip_suffix(']'))
+            .ok_or_else(|| format!("{:?} is not mask or mem[<address>]", target))?;
+        Ok(Instruction::Write {
+            address: address
+                .parse()
+                .map_err(|_| format!("{:?} is not an address", address))?,
+            value: value
+                .parse()
+                .map_err(|_| format!("{:?} is not a value", value))?,
+        })
+    }
+}
+
+/// A set of addresses that match `bits` everywhere except the `floating` bits, which
+/// can be either 0 or 1
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Pattern {
+    /// The fixed bits, with the floating ones left as 0
+    bits: u64,
+    floating: u64,
+}
+
+impl Pattern {
+    fn new(bits: u64, floating: u64) -> Self {
+        Pattern {
+            bits: bits & !floating,
+            floating,
+        }
+    }
+
+    /// How many addresses match
+    fn len(self) -> u128 {
+        1 << self.floating.count_ones()
+    }
+
+    #[cfg(test)]
+    fn contains(self, address: u64) -> bool {
+        address & !self.floating == self.bits
+    }
+
+    /// Whether any address matches both patterns
+    fn overlaps(self, other: Pattern) -> bool {
+        let fixed_in_both = !self.floating & !other.floating;
+        (self.bits ^ other.bits) & fixed_in_both == 0
+    }
+
+    /// The addresses that match this pattern but not `other`, as patterns that don't
+    /// overlap each other. There is at most one for each bit that floats here but not in
+    /// `other`.
+    fn subtract(self, other: Pattern) -> Vec<Pattern> {
+        if !self.overlaps(other) {
+            return vec![self];
+        }
+        let mut pieces = Vec::new();
+        let mut rest = self;
+        let mut split_bits = self.floating & !other.floating;
+        while split_bits != 0 {
+            let bit = split_bits & split_bits.wrapping_neg();
+            split_bits &= !bit;
+            let floating = rest.floating & !bit;
+            // Fix the bit the opposite way to `other`, so that piece misses it, and keep
+            // narrowing down the rest towards `other`
+            pieces.push(Pattern::new(rest.bits | (!other.bits & bit), floating));
+            rest = Pattern::new(rest.bits | (other.bits & bit), floating);
+        }
+        // What is left is inside `other`
+        pieces
+    }
+}
+
+/// Memory where a write can go to many addresses at once. Each write is kept as a
+/// pattern of addresses, cut down as later writes cover parts of it, so it doesn't
+/// matter how many addresses a pattern matches.
+#[derive(Debug, Clone, Default)]
+struct FloatingMemory {
+    /// Patterns that don't overlap, with the value at each of their addresses
+    regions: Vec<(Pattern, u64)>,
+}
+
+impl FloatingMemory {
+    /// Write the value to every address matching the pattern
+    fn write(&mut self, pattern: Pattern, value: u64) {
+        let mut regions = Vec::with_capacity(self.regions.len() + 1);
+        for &(region, old) in &self.regions {
+            regions.extend(region.subtract(pattern).into_iter().map(|r| (r, old)));
+        }
+        regions.push((pattern, value));
+        self.regions = regions;
+    }
+
+    /// The value at an address, which starts off as 0
+    #[cfg(test)]
+    fn get(&self, address: u64) -> u64 {
+        self.regions
+            .iter()
+            .find(|(region, _)| region.contains(address))
+            .map_or(0, |&(_, value)| value)
+    }
+
+    /// The sum of every value in memory
+    fn sum(&self) -> u128 {
+        self.regions
+            .iter()
+            .map(|&(region, value)| region.len() * value as u128)
+            .sum()
+    }
+}
+
+/// How the mask changes a write
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Decoder {
+    /// Version 1: the mask sets and clears bits of the value
+    Value,
+    /// Version 2: the mask sets bits of the address, and its X bits float
+    Address,
+}
+
+/// Run the program and give back the memory it leaves
+fn run(program: &[Instruction], decoder: Decoder) -> FloatingMemory {
+    let mut memory = FloatingMemory::default();
+    let mut mask = Mask::default();
+    for instruction in program {
+        match *instruction {
+            Instruction::SetMask(m) => mask = m,
+            Instruction::Write { address, value } => match decoder {
+                Decoder::Value => {
+                    memory.write(Pattern::new(address, 0), (value | mask.ones) & !mask.zeros)
+                }
+                Decoder::Address => {
+                    memory.write(Pattern::new(address | mask.ones, mask.floating), value)
+                }
+            },
+        }
+    }
+    memory
+}
+
+fn part1(program: &[Instruction]) -> u128 {
+    run(program, Decoder::Value).sum()
+}
+
+fn part2(program: &[Instruction]) -> u128 {
+    run(program, Decoder::Address).sum()
+}
+
+fn main() {
+    let setup_time = std::time::Instant::now();
+    let input_str =
+        std::fs::read_to_string("inputs/day14.txt").expect("Could not read day 14 input file");
+    let program: Vec<Instruction> = input_str
+        .lines()
+        .enumerate()
+        .map(|(idx, l)| Instruction::parse(l).unwrap_or_else(|e| panic!("Line {}: {}", idx + 1, e)))
+        .collect();
+    println!(
+        "Setup took {:.6} microseconds",
+        setup_time.elapsed().as_micros()
+    );
+
+    let part1_time = std::time::Instant::now();
+    let part1_solution = part1(&program);
+    println!(
+        "Part 1 took {:.6} microseconds",
+        part1_time.elapsed().as_micros()
+    );
+
+    let part2_time = std::time::Instant::now();
+    let part2_solution = part2(&program);
+    println!(
+        "Part 2 took {:.6} microseconds",
+        part2_time.elapsed().as_micros()
+    );
+
+    println!();
+    println!("First puzzle: {}", part1_solution);
+    println!("Second puzzle: {}", part2_solution);
+}
+
+#[cfg(test)]
+fn parse_program(input: &str) -> Vec<Instruction> {
+    input
+        .lines()
+        .map(|l| Instruction::parse(l).expect("Could not parse instruction"))
+        .collect()
+}
+
+#[test]
+fn test_part1() {
+    let program = parse_program(
+        "mask = XXXXXXXXXXXXXXXXXXXXXXXXXXXXX1XXXX0X
+mem[8] = 11
+mem[7] = 101
+mem[8] = 0
+",
+    );
+    let memory = run(&program, Decoder::Value);
+    assert_eq!(memory.get(7), 101);
+    assert_eq!(memory.get(8), 64);
+    assert_eq!(memory.get(9), 0);
+    assert_eq!(part1(&program), 165);
+}
+
+#[test]
+fn test_part2() {
+    let program = parse_program(
+        "mask = 000000000000000000000000000000X1001X
+mem[42] = 100
+mask = 00000000000000000000000000000000X0XX
+mem[26] = 1
+",
+    );
+    let memory = run(&program, Decoder::Address);
+    assert_eq!(memory.get(58), 100);
+    assert_eq!(memory.get(59), 100);
+    assert_eq!(memory.get(16), 1);
+    assert_eq!(memory.get(27), 1);
+    assert_eq!(part2(&program), 208);
+}
+
+#[test]
+fn test_many_floating_bits() {
+    // 2^36 addresses written and then mostly overwritten, without visiting them one by one
+    let program = parse_program(
+        "mask = XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX
+mem[0] = 3
+mask = 0XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX
+mem[0] = 1
+mask = 000000000000000000000000000000000001
+mem[0] = 7
+",
+    );
+    let memory = run(&program, Decoder::Address);
+    let half = 1u128 << 35;
+    assert_eq!(part2(&program), half * 3 + (half - 1) + 7);
+    assert_eq!(memory.get(1), 7);
+    assert_eq!(memory.get(2), 1);
+    assert_eq!(memory.get(1 << 35), 3);
+}
+
+#[test]
+fn test_subtract() {
+    let all = Pattern::new(0, 0b111);
+    let pieces = all.subtract(Pattern::new(0b101, 0b010));
+    assert_eq!(pieces.iter().map(|p| p.len()).sum::<u128>(), 6);
+    for address in 0..8 {
+        let hits = pieces.iter().filter(|p| p.contains(address)).count();
+        let expected = usize::from(address & 0b101 != 0b101);
+        assert_eq!(hits, expected, "for address {:03b}", address);
+    }
+    assert_eq!(all.subtract(all), vec![]);
+    let one = Pattern::new(0b100, 0);
+    assert_eq!(one.subtract(Pattern::new(0, 0b011)), vec![one]);
+}
+
+#[test]
+fn test_parse_errors() {
+    assert!(Instruction::parse("mask = 1X0").is_err());
+    assert!(Instruction::parse("mask = XXXXXXXXXXXXXXXXXXXXXXXXXXXXX2XXXX0X").is_err());
+    assert!(Instruction::parse("mem[x] = 1").is_err());
+    assert!(Instruction::parse("mem[1] 1").is_err());
+    assert!(Instruction::parse("reg[1] = 1").is_err());
+}
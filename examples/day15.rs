@@ -0,0 +1,144 @@
+/// The numbers spoken in the elves' memory game, which is a Van Eck sequence after the
+/// starting numbers. Each number after those is 0 if the last number was new, or else how
+/// many turns apart the last number and the time before it was spoken are.
+#[derive(Debug, Clone)]
+struct MemoryGame {
+    starting: Vec<u32>,
+    /// How many numbers have been spoken so far
+    turn: u32,
+    /// The number to speak next, once the starting numbers are used up
+    next: u32,
+    /// For each number, the turn it was last spoken on, counting from 1, or 0 if it
+    /// hasn't been yet
+    last_seen: Vec<u32>,
+}
+
+impl MemoryGame {
+    fn new(starting: &[u32]) -> Self {
+        MemoryGame::with_capacity(starting, 0)
+    }
+
+    /// A game with room for the numbers spoken in the first `turns` turns, to save
+    /// growing the table on the way. Every number spoken is less than the turn it is
+    /// spoken on, unless it is a starting number.
+    fn with_capacity(starting: &[u32], turns: usize) -> Self {
+        let largest = starting.iter().max().map_or(0, |&n| n as usize + 1);
+        MemoryGame {
+            starting: starting.to_vec(),
+            turn: 0,
+            next: 0,
+            last_seen: vec![0; largest.max(turns)],
+        }
+    }
+}
+
+impl Iterator for MemoryGame {
+    type Item = u32;
+
+    fn next(&mut self) -> Option<u32> {
+        let spoken = self
+            .starting
+            .get(self.turn as usize)
+            .copied()
+            .unwrap_or(self.next);
+        self.turn = self.turn.checked_add(1)?;
+        let idx = spoken as usize;
+        if idx >= self.last_seen.len() {
+            self.last_seen
+                .resize((idx + 1).max(self.last_seen.len() * 2), 0);
+        }
+        let before = std::mem::replace(&mut self.last_seen[idx], self.turn);
+        self.next = if before == 0 { 0 } else { self.turn - before };
+        Some(spoken)
+    }
+}
+
+/// The number spoken on the given turn, counting from 1
+fn nth_spoken(starting: &[u32], turn: usize) -> Option<u32> {
+    MemoryGame::with_capacity(starting, turn).nth(turn.checked_sub(1)?)
+}
+
+fn part1(starting: &[u32]) -> Option<u32> {
+    nth_spoken(starting, 2020)
+}
+
+fn part2(starting: &[u32]) -> Option<u32> {
+    nth_spoken(starting, 30_000_000)
+}
+
+fn main() {
+    let setup_time = std::time::Instant::now();
+    let input_str =
+        std::fs::read_to_string("inputs/day15.txt").expect("Could not read day 15 input file");
+    let starting: Vec<u32> = input_str
+        .trim()
+        .split(',')
+        .map(|n| n.parse().expect("Could not parse starting number"))
+        .collect();
+    println!(
+        "Setup took {:.6} microseconds",
+        setup_time.elapsed().as_micros()
+    );
+
+    let part1_time = std::time::Instant::now();
+    let part1_solution = part1(&starting).expect("The game ended early");
+    println!(
+        "Part 1 took {:.6} microseconds",
+        part1_time.elapsed().as_micros()
+    );
+
+    let part2_time = std::time::Instant::now();
+    let part2_solution = part2(&starting).expect("The game ended early");
+    println!(
+        "Part 2 took {:.6} microseconds",
+        part2_time.elapsed().as_micros()
+    );
+
+    println!();
+    println!("First puzzle: {}", part1_solution);
+    println!("Second puzzle: {}", part2_solution);
+    println!(
+        "The first ten numbers spoken: {:?}",
+        MemoryGame::new(&starting).take(10).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_sequence() {
+    assert_eq!(
+        MemoryGame::new(&[0, 3, 6]).take(10).collect::<Vec<_>>(),
+        vec![0, 3, 6, 0, 3, 3, 1, 0, 4, 0]
+    );
+    // With no starting numbers, it is the Van Eck sequence itself
+    assert_eq!(
+        MemoryGame::new(&[]).take(12).collect::<Vec<_>>(),
+        vec![0, 0, 1, 0, 2, 0, 2, 2, 1, 6, 0, 5]
+    );
+    // A repeated starting number counts as spoken before
+    assert_eq!(
+        MemoryGame::new(&[5, 5]).take(4).collect::<Vec<_>>(),
+        vec![5, 5, 1, 0]
+    );
+    assert_eq!(nth_spoken(&[0, 3, 6], 0), None);
+}
+
+#[test]
+fn test_part1() {
+    let examples = [
+        ([0, 3, 6], 436),
+        ([1, 3, 2], 1),
+        ([2, 1, 3], 10),
+        ([1, 2, 3], 27),
+        ([2, 3, 1], 78),
+        ([3, 2, 1], 438),
+        ([3, 1, 2], 1836),
+    ];
+    for (starting, spoken) in examples {
+        assert_eq!(part1(&starting), Some(spoken), "for {:?}", starting);
+    }
+}
+
+#[test]
+fn test_part2() {
+    assert_eq!(part2(&[0, 3, 6]), Some(175594));
+}
@@ -0,0 +1,437 @@
+use rs_aoc::records::{records, Record};
+use std::ops::RangeInclusive;
+
+/// A field on a ticket, and the ranges its value can be in
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct FieldRule<'a> {
+    name: &'a str,
+    ranges: Vec<RangeInclusive<u64>>,
+}
+
+impl<'a> FieldRule<'a> {
+    /// Read a rule like `departure location: 49-258 or 268-960`, which can have any
+    /// number of ranges
+    fn parse(line: &'a str) -> Result<Self, String> {
+        let (name, ranges) = line
+            .split_once(": ")
+            .ok_or_else(|| "Expected \"<field>: <ranges>\"".to_string())?;
+        let ranges = ranges
+            .split(" or ")
+            .map(|range| {
+                let (start, end) = range
+                    .split_once('-')
+                    .ok_or_else(|| format!("{:?} is not a range like 1-3", range))?;
+                let number = |n: &str| {
+                    n.parse::<u64>()
+                        .map_err(|_| format!("{:?} is not a number", n))
+                };
+                Ok(number(start)?..=number(end)?)
+            })
+            .collect::<Result<_, String>>()?;
+        Ok(FieldRule { name, ranges })
+    }
+
+    fn allows(&self, value: u64) -> bool {
+        self.ranges.iter().any(|r| r.contains(&value))
+    }
+}
+
+/// Everything in the notes: the rules, our ticket and the tickets nearby
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Notes<'a> {
+    rules: Vec<FieldRule<'a>>,
+    mine: Vec<u64>,
+    nearby: Vec<Vec<u64>>,
+}
+
+fn parse_ticket(line: &str) -> Result<Vec<u64>, String> {
+    line.split(',')
+        .map(|n| n.parse().map_err(|_| format!("{:?} is not a number", n)))
+        .collect()
+}
+
+impl<'a> Notes<'a> {
+    /// Read the three sections of the notes, which are separated by blank lines
+    fn parse(input: &'a str) -> Result<Self, String> {
+        let sections: Vec<_> = records(input).collect();
+        let [rules, mine, nearby] = &sections[..] else {
+            return Err(format!(
+                "Expected rules, your ticket and nearby tickets, but found {} sections",
+                sections.len()
+            ));
+        };
+        fn in_section<T>(line: usize, result: Result<T, String>) -> Result<T, String> {
+            result.map_err(|e| format!("Line {}: {}", line, e))
+        }
+        let rules = rules
+            .lines
+            .iter()
+            .enumerate()
+            .map(|(idx, l)| in_section(rules.line + idx, FieldRule::parse(l)))
+            .collect::<Result<_, _>>()?;
+        let tickets = |record: &Record<&str>, heading| {
+            if record.lines.first() != Some(&heading) {
+                return Err(format!("Line {}: Expected {:?}", record.line, heading));
+            }
+            record.lines[1..]
+                .iter()
+                .enumerate()
+                .map(|(idx, l)| in_section(record.line + idx + 1, parse_ticket(l)))
+                .collect::<Result<Vec<_>, _>>()
+        };
+        let mine_line = mine.line + 1;
+        let mine = match <[Vec<u64>; 1]>::try_from(tickets(mine, "your ticket:")?) {
+            Ok([ticket]) => ticket,
+            Err(found) => {
+                return Err(format!(
+                    "Line {}: Expected exactly one ticket under \"your ticket:\", but found {}",
+                    mine.line,
+                    found.len()
+                ))
+            }
+        };
+        let notes = Notes {
+            rules,
+            mine,
+            nearby: tickets(nearby, "nearby tickets:")?,
+        };
+        // Our ticket is always used to work out the fields, so a bad value on it would
+        // only show up later as some field having no column
+        if let Some(value) = notes.mine.iter().find(|&&v| !notes.any_rule_allows(v)) {
+            return Err(format!(
+                "Line {}: {} on your ticket is not allowed by any rule",
+                mine_line, value
+            ));
+        }
+        Ok(notes)
+    }
+
+    /// Whether any rule at all allows the value
+    fn any_rule_allows(&self, value: u64) -> bool {
+        self.rules.iter().any(|r| r.allows(value))
+    }
+
+    /// The sum of the values on nearby tickets that no rule allows
+    fn error_rate(&self) -> u64 {
+        self.nearby
+            .iter()
+            .flatten()
+            .filter(|&&v| !self.any_rule_allows(v))
+            .sum()
+    }
+
+    /// The nearby tickets where every value is allowed by some rule
+    fn valid_nearby(&self) -> impl Iterator<Item = &Vec<u64>> {
+        self.nearby
+            .iter()
+            .filter(|t| t.iter().all(|&v| self.any_rule_allows(v)))
+    }
+}
+
+/// Why the fields couldn't each be given a column
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum SolveError<'a> {
+    /// There is no way to give every field a column of its own. This field is one that
+    /// is left without one.
+    Contradiction(&'a str),
+    /// There is more than one way to do it. These are the fields that could go in more
+    /// than one column, with the columns each could go in.
+    Ambiguous(Vec<(&'a str, Vec<usize>)>),
+}
+
+impl std::fmt::Display for SolveError<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SolveError::Contradiction(field) => {
+                write!(f, "There is no column left for the {:?} field", field)
+            }
+            SolveError::Ambiguous(fields) => {
+                write!(f, "Fields could go in more than one column:")?;
+                for (field, columns) in fields {
+                    write!(f, " {:?} in {:?}", field, columns)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for SolveError<'_> {}
+
+/// Try to give `field` a column, moving the fields that already have columns along if
+/// need be. `owner` holds the field in each column, and `seen` the columns tried so far.
+fn place(
+    field: usize,
+    candidates: &[Vec<bool>],
+    seen: &mut [bool],
+    owner: &mut [Option<usize>],
+) -> bool {
+    for col in 0..owner.len() {
+        if candidates[field][col] && !seen[col] {
+            seen[col] = true;
+            if owner[col].is_none_or(|other| place(other, candidates, seen, owner)) {
+                owner[col] = Some(field);
+                return true;
+            }
+        }
+    }
+    false
+}
+
+/// Give every field a column of its own, if there's a way to, as a bipartite matching.
+/// Gives back a field that couldn't be placed if not.
+fn match_fields(candidates: &[Vec<bool>], columns: usize) -> Result<(), usize> {
+    let mut owner = vec![None; columns];
+    for field in 0..candidates.len() {
+        if !place(field, candidates, &mut vec![false; columns], &mut owner) {
+            return Err(field);
+        }
+    }
+    Ok(())
+}
+
+/// Work out which column each field is in, where `candidates[field][col]` says whether
+/// the field could be in the column. Gives back the column for each field.
+///
+/// First, any field with only one column left takes it, so no other field can have that
+/// column, and so on until that settles. If that leaves fields with a choice, every
+/// choice is tried with a matching of the rest, to throw out the ones that lead nowhere.
+fn assign_columns<'a>(
+    fields: &[&'a str],
+    mut candidates: Vec<Vec<bool>>,
+) -> Result<Vec<usize>, SolveError<'a>> {
+    let columns = candidates.first().map_or(0, Vec::len);
+    let options = |row: &Vec<bool>| -> Vec<usize> { (0..columns).filter(|&c| row[c]).collect() };
+
+    let mut settled = vec![false; fields.len()];
+    while let Some(field) =
+        (0..fields.len()).find(|&f| !settled[f] && options(&candidates[f]).len() <= 1)
+    {
+        let col = *options(&candidates[field])
+            .first()
+            .ok_or(SolveError::Contradiction(fields[field]))?;
+        settled[field] = true;
+        for (other, row) in candidates.iter_mut().enumerate() {
+            if other != field {
+                row[col] = false;
+            }
+        }
+    }
+
+    match_fields(&candidates, columns).map_err(|f| SolveError::Contradiction(fields[f]))?;
+    for field in (0..fields.len()).filter(|&f| !settled[f]) {
+        for col in options(&candidates[field]) {
+            let mut forced = candidates.clone();
+            for (other, row) in forced.iter_mut().enumerate() {
+                if other == field {
+                    row.iter_mut()
+                        .enumerate()
+                        .for_each(|(c, ok)| *ok = c == col);
+                } else {
+                    row[col] = false;
+                }
+            }
+            if match_fields(&forced, columns).is_err() {
+                candidates[field][col] = false;
+            }
+        }
+    }
+
+    let choices: Vec<Vec<usize>> = candidates.iter().map(options).collect();
+    let ambiguous: Vec<(&str, Vec<usize>)> = choices
+        .iter()
+        .enumerate()
+        .filter(|(_, cols)| cols.len() > 1)
+        .map(|(f, cols)| (fields[f], cols.clone()))
+        .collect();
+    if !ambiguous.is_empty() {
+        return Err(SolveError::Ambiguous(ambiguous));
+    }
+    Ok(choices.iter().map(|cols| cols[0]).collect())
+}
+
+/// Which column each rule's field is in, going by the valid nearby tickets and our own
+fn solve_fields<'a>(notes: &Notes<'a>) -> Result<Vec<usize>, SolveError<'a>> {
+    let tickets: Vec<&Vec<u64>> = notes
+        .valid_nearby()
+        .chain(std::iter::once(&notes.mine))
+        .collect();
+    let candidates = notes
+        .rules
+        .iter()
+        .map(|rule| {
+            (0..notes.mine.len())
+                .map(|col| {
+                    tickets
+                        .iter()
+                        .all(|t| t.get(col).is_some_and(|&v| rule.allows(v)))
+                })
+                .collect()
+        })
+        .collect();
+    let fields: Vec<&str> = notes.rules.iter().map(|r| r.name).collect();
+    assign_columns(&fields, candidates)
+}
+
+fn part1(notes: &Notes) -> u64 {
+    notes.error_rate()
+}
+
+fn part2<'a>(notes: &Notes<'a>) -> Result<u64, SolveError<'a>> {
+    let columns = solve_fields(notes)?;
+    Ok(notes
+        .rules
+        .iter()
+        .zip(columns)
+        .filter(|(rule, _)| rule.name.starts_with("departure"))
+        .map(|(_, col)| notes.mine[col])
+        .product())
+}
+
+fn main() {
+    let setup_time = std::time::Instant::now();
+    let input_str =
+        std::fs::read_to_string("inputs/day16.txt").expect("Could not read day 16 input file");
+    let notes = Notes::parse(&input_str).unwrap_or_else(|e| panic!("{}", e));
+    println!(
+        "Setup took {:.6} microseconds",
+        setup_time.elapsed().as_micros()
+    );
+
+    let part1_time = std::time::Instant::now();
+    let part1_solution = part1(&notes);
+    println!(
+        "Part 1 took {:.6} microseconds",
+        part1_time.elapsed().as_micros()
+    );
+
+    let part2_time = std::time::Instant::now();
+    let part2_solution = part2(&notes).unwrap_or_else(|e| panic!("{}", e));
+    println!(
+        "Part 2 took {:.6} microseconds",
+        part2_time.elapsed().as_micros()
+    );
+
+    println!();
+    println!("First puzzle: {}", part1_solution);
+    println!("Second puzzle: {}", part2_solution);
+}
+
+#[test]
+fn test_part1() {
+    let input = "class: 1-3 or 5-7
+row: 6-11 or 33-44
+seat: 13-40 or 45-50
+
+your ticket:
+7,1,14
+
+nearby tickets:
+7,3,47
+40,4,50
+55,2,20
+38,6,12
+";
+    let notes = Notes::parse(input).expect("Could not parse notes");
+    assert_eq!(notes.rules[1].ranges, vec![6..=11, 33..=44]);
+    assert_eq!(notes.mine, vec![7, 1, 14]);
+    assert_eq!(part1(&notes), 71);
+    assert_eq!(notes.valid_nearby().count(), 1);
+}
+
+#[test]
+fn test_part2() {
+    let input = "class: 0-1 or 4-19
+row: 0-5 or 8-19
+seat: 0-13 or 16-19
+
+your ticket:
+11,12,13
+
+nearby tickets:
+3,9,18
+15,1,5
+5,14,9
+";
+    let notes = Notes::parse(input).expect("Could not parse notes");
+    assert_eq!(solve_fields(&notes), Ok(vec![1, 0, 2]));
+    assert_eq!(
+        part2(&notes),
+        Ok(1),
+        "No departure fields, so the product is empty"
+    );
+}
+
+#[test]
+fn test_assign_columns() {
+    let fields = ["a", "b", "c"];
+    // No field is down to one column, but b has to take the only column a and c can't
+    let candidates = vec![
+        vec![true, true, false],
+        vec![true, true, true],
+        vec![true, true, false],
+    ];
+    assert_eq!(
+        assign_columns(&fields, candidates),
+        Err(SolveError::Ambiguous(vec![
+            ("a", vec![0, 1]),
+            ("c", vec![0, 1])
+        ]))
+    );
+
+    let candidates = vec![
+        vec![true, false, false],
+        vec![true, true, true],
+        vec![true, true, false],
+    ];
+    assert_eq!(assign_columns(&fields, candidates), Ok(vec![0, 2, 1]));
+
+    // a and c both need column 0
+    let candidates = vec![
+        vec![true, false, false],
+        vec![true, true, true],
+        vec![true, false, false],
+    ];
+    assert_eq!(
+        assign_columns(&fields, candidates),
+        Err(SolveError::Contradiction("c"))
+    );
+
+    // a and b fight over columns 0 and 1 with c, which has nowhere else to go
+    let candidates = vec![
+        vec![true, true, false],
+        vec![true, true, false],
+        vec![true, true, false],
+    ];
+    assert!(matches!(
+        assign_columns(&fields, candidates),
+        Err(SolveError::Contradiction(_))
+    ));
+}
+
+#[test]
+fn test_parse_errors() {
+    assert!(FieldRule::parse("class 1-3").is_err());
+    assert!(FieldRule::parse("class: 1-3 or 5").is_err());
+    assert_eq!(
+        FieldRule::parse("odd: 1-1 or 3-3 or 5-5").map(|r| r.ranges.len()),
+        Ok(3)
+    );
+    assert!(Notes::parse("class: 1-3\n\nyour ticket:\n1\n").is_err());
+    assert_eq!(
+        Notes::parse("class: 1-3\n\nyour ticket:\n1\n\nnearby tickets:\n1,x\n"),
+        Err("Line 7: \"x\" is not a number".to_string())
+    );
+    assert_eq!(
+        Notes::parse("class: 1-3\n\nyour ticket:\n1\n2\n\nnearby tickets:\n1\n"),
+        Err("Line 3: Expected exactly one ticket under \"your ticket:\", but found 2".to_string())
+    );
+    assert_eq!(
+        Notes::parse("class: 1-3\n\nyour ticket:\n\nnearby tickets:\n1\n"),
+        Err("Line 3: Expected exactly one ticket under \"your ticket:\", but found 0".to_string())
+    );
+    assert_eq!(
+        Notes::parse("class: 1-3\nrow: 5-7\n\nyour ticket:\n2,4\n\nnearby tickets:\n2,5\n"),
+        Err("Line 5: 4 on your ticket is not allowed by any rule".to_string())
+    );
+}
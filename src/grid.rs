@@ -0,0 +1,168 @@
+//! Grids of cells that are either set or not, like the trees on day 3's slope
+
+/// A rectangular grid of cells that are each either set or not, such as where the trees
+/// are on a slope.
+///
+/// Each row is a bitset of `u64` words, and all the rows are kept in one allocation, so
+/// a cell takes up a single bit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeField {
+    width: usize,
+    height: usize,
+    /// How many words each row takes up in `bits`
+    row_words: usize,
+    bits: Vec<u64>,
+}
+
+impl TreeField {
+    /// An empty field, with no cells set
+    pub fn new(width: usize, height: usize) -> Self {
+        let row_words = width.div_ceil(64);
+        TreeField {
+            width,
+            height,
+            row_words,
+            bits: vec![0; row_words * height],
+        }
+    }
+
+    /// Build a field from rows of cells, which must all be the same width
+    pub fn from_rows<R, C>(rows: R) -> Self
+    where
+        R: IntoIterator<Item = C>,
+        C: IntoIterator<Item = bool>,
+    {
+        let mut field = TreeField::new(0, 0);
+        // Pack each row into words here first, since its width isn't known until the end
+        let mut row_bits: Vec<u64> = Vec::new();
+        for row in rows {
+            row_bits.clear();
+            let mut width = 0;
+            for cell in row {
+                if width % 64 == 0 {
+                    row_bits.push(0);
+                }
+                if cell {
+                    row_bits[width / 64] |= 1 << (width % 64);
+                }
+                width += 1;
+            }
+
+            if field.height == 0 {
+                field.width = width;
+                field.row_words = row_bits.len();
+            }
+            assert_eq!(
+                width, field.width,
+                "Row {} is not the same width as the first",
+                field.height
+            );
+
+            field.bits.extend_from_slice(&row_bits);
+            field.height += 1;
+        }
+        field
+    }
+
+    /// Parse a map where `#` marks a tree and anything else is open ground
+    pub fn parse(input: &str) -> Self {
+        TreeField::from_rows(input.lines().map(|row| row.chars().map(|c| c == '#')))
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Which word in `bits` holds a cell, and which bit of that word it is
+    fn locate(&self, row: usize, col: usize) -> (usize, u64) {
+        (row * self.row_words + col / 64, 1 << (col % 64))
+    }
+
+    /// Whether the cell is set, or `None` if it is outside the field
+    pub fn get(&self, row: usize, col: usize) -> Option<bool> {
+        if row >= self.height || col >= self.width {
+            return None;
+        }
+        let (word, mask) = self.locate(row, col);
+        Some(self.bits[word] & mask != 0)
+    }
+
+    /// Whether the cell is set, treating the field as repeating forever to the right.
+    ///
+    /// Panics if `row` is past the bottom of the field, or the field has no columns.
+    pub fn get_wrapping(&self, row: usize, col: usize) -> bool {
+        assert!(row < self.height, "Row {} is outside the field", row);
+        let (word, mask) = self.locate(row, col % self.width);
+        self.bits[word] & mask != 0
+    }
+
+    /// Set or clear a cell. Panics if it is outside the field.
+    pub fn set(&mut self, row: usize, col: usize, value: bool) {
+        assert!(
+            row < self.height && col < self.width,
+            "({}, {}) is outside the field",
+            row,
+            col
+        );
+        let (word, mask) = self.locate(row, col);
+        if value {
+            self.bits[word] |= mask;
+        } else {
+            self.bits[word] &= !mask;
+        }
+    }
+
+    /// How many cells are set
+    pub fn count(&self) -> usize {
+        self.bits.iter().map(|w| w.count_ones() as usize).sum()
+    }
+}
+
+#[test]
+fn test_parse() {
+    let field = TreeField::parse("..#\n#..\n");
+    assert_eq!(field.width(), 3);
+    assert_eq!(field.height(), 2);
+    assert_eq!(field.get(0, 2), Some(true));
+    assert_eq!(field.get(0, 1), Some(false));
+    assert_eq!(field.get(1, 0), Some(true));
+    assert_eq!(field.get(2, 0), None);
+    assert_eq!(field.get(0, 3), None);
+    assert_eq!(field.count(), 2);
+}
+
+#[test]
+fn test_wrapping() {
+    let field = TreeField::parse("#..\n.#.");
+    assert!(field.get_wrapping(0, 3));
+    assert!(field.get_wrapping(1, 301));
+    assert!(!field.get_wrapping(1, 302));
+}
+
+#[test]
+fn test_wide_rows() {
+    // Rows that span more than one word, with the last one partly used
+    let mut field = TreeField::new(130, 3);
+    field.set(1, 0, true);
+    field.set(1, 63, true);
+    field.set(1, 64, true);
+    field.set(2, 129, true);
+    assert_eq!(field.count(), 4);
+    assert_eq!(field.get(0, 129), Some(false));
+    assert_eq!(field.get(1, 64), Some(true));
+    assert!(field.get_wrapping(2, 259));
+
+    field.set(1, 63, false);
+    assert_eq!(field.get(1, 63), Some(false));
+    assert_eq!(field.count(), 3);
+}
+
+#[test]
+#[should_panic]
+fn test_ragged_rows() {
+    TreeField::parse("..#\n#.");
+}
@@ -0,0 +1,7 @@
+//! Code shared between the solutions in `examples/`
+
+pub mod grid;
+pub mod modular;
+pub mod password;
+pub mod records;
+pub mod vec2;
@@ -0,0 +1,210 @@
+//! Modular arithmetic, like solving for day 13's bus timetable with the Chinese Remainder
+//! Theorem
+
+/// The greatest common divisor `g` of `a` and `b`, along with `x` and `y` such that
+/// `a * x + b * y == g`. The divisor is never negative.
+///
+/// Panics if the answer can't be held in an `i128`, which only happens when `a` or `b`
+/// is `i128::MIN`.
+pub fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    let (mut old_r, mut r) = (a, b);
+    let (mut old_x, mut x) = (1, 0);
+    let (mut old_y, mut y) = (0, 1);
+    while r != 0 {
+        let q = old_r / r;
+        (old_r, r) = (r, old_r - q * r);
+        (old_x, x) = (x, old_x - q * x);
+        (old_y, y) = (y, old_y - q * y);
+    }
+    if old_r < 0 {
+        (-old_r, -old_x, -old_y)
+    } else {
+        (old_r, old_x, old_y)
+    }
+}
+
+/// The `x` in `0..m` with `a * x` congruent to 1 mod `m`, if there is one
+pub fn mod_inverse(a: i128, m: i128) -> Option<i128> {
+    if m <= 0 {
+        return None;
+    }
+    let (g, x, _) = extended_gcd(a.rem_euclid(m), m);
+    (g == 1).then(|| x.rem_euclid(m))
+}
+
+/// `(a + b) mod m` for `a` and `b` in `0..m`, without overflowing
+fn add_mod(a: i128, b: i128, m: i128) -> i128 {
+    if a >= m - b {
+        a - (m - b)
+    } else {
+        a + b
+    }
+}
+
+/// `a * b mod m`, in `0..m`. Works by doubling and adding, so it never overflows even
+/// when the product itself would.
+///
+/// Panics if `m` is not positive.
+pub fn mul_mod(a: i128, b: i128, m: i128) -> i128 {
+    assert!(m > 0, "Modulus {} is not positive", m);
+    let (mut a, mut b) = (a.rem_euclid(m), b.rem_euclid(m));
+    let mut product = 0;
+    while b > 0 {
+        if b & 1 == 1 {
+            product = add_mod(product, a, m);
+        }
+        a = add_mod(a, a, m);
+        b >>= 1;
+    }
+    product
+}
+
+/// The numbers that leave `residue` when divided by `modulus`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Congruence {
+    pub residue: i128,
+    pub modulus: i128,
+}
+
+impl Congruence {
+    /// The numbers congruent to `residue` mod `modulus`. The residue is kept in
+    /// `0..modulus` so equal congruences compare equal.
+    ///
+    /// Panics if `modulus` is not positive.
+    pub fn new(residue: i128, modulus: i128) -> Self {
+        assert!(modulus > 0, "Modulus {} is not positive", modulus);
+        Congruence {
+            residue: residue.rem_euclid(modulus),
+            modulus,
+        }
+    }
+
+    /// Whether `n` is one of the numbers
+    pub fn contains(&self, n: i128) -> bool {
+        n.rem_euclid(self.modulus) == self.residue
+    }
+
+    /// The numbers in both congruences, as a single congruence mod the lowest common
+    /// multiple of the moduli, which don't need to be coprime
+    pub fn intersect(self, other: Congruence) -> Result<Congruence, CrtError> {
+        let (g, p, _) = extended_gcd(self.modulus, other.modulus);
+        let diff = other
+            .residue
+            .checked_sub(self.residue)
+            .ok_or(CrtError::Overflow)?;
+        if diff % g != 0 {
+            return Err(CrtError::NoSolution(self, other));
+        }
+        let step = other.modulus / g;
+        let lcm = (self.modulus / g)
+            .checked_mul(other.modulus)
+            .ok_or(CrtError::Overflow)?;
+        // self.modulus * p is congruent to g mod other.modulus, so adding
+        // self.modulus * p * diff / g to self.residue makes it match other.residue too
+        let t = mul_mod(diff / g, p, step);
+        let residue = self
+            .modulus
+            .checked_mul(t)
+            .and_then(|n| n.checked_add(self.residue))
+            .ok_or(CrtError::Overflow)?;
+        Ok(Congruence::new(residue, lcm))
+    }
+}
+
+/// Why a set of congruences couldn't be solved
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrtError {
+    /// No number is in both of these
+    NoSolution(Congruence, Congruence),
+    /// A modulus was zero or negative
+    BadModulus(i128),
+    /// The numbers got too big for an `i128`
+    Overflow,
+}
+
+impl std::fmt::Display for CrtError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CrtError::NoSolution(a, b) => write!(
+                f,
+                "No number is {} mod {} and {} mod {}",
+                a.residue, a.modulus, b.residue, b.modulus
+            ),
+            CrtError::BadModulus(m) => write!(f, "Modulus {} is not positive", m),
+            CrtError::Overflow => write!(f, "The numbers are too big for an i128"),
+        }
+    }
+}
+
+impl std::error::Error for CrtError {}
+
+/// Solve a set of `(residue, modulus)` pairs with the Chinese Remainder Theorem, giving
+/// the numbers congruent to every residue mod its modulus. The moduli don't need to be
+/// coprime. With no pairs at all, every number is a solution.
+pub fn solve_crt<I>(pairs: I) -> Result<Congruence, CrtError>
+where
+    I: IntoIterator<Item = (i128, i128)>,
+{
+    pairs
+        .into_iter()
+        .try_fold(Congruence::new(0, 1), |acc, (residue, modulus)| {
+            if modulus <= 0 {
+                return Err(CrtError::BadModulus(modulus));
+            }
+            acc.intersect(Congruence::new(residue, modulus))
+        })
+}
+
+#[test]
+fn test_extended_gcd() {
+    for (a, b) in [(240, 46), (46, 240), (-240, 46), (17, 0), (0, 0), (7, 13)] {
+        let (g, x, y) = extended_gcd(a, b);
+        assert!(g >= 0);
+        assert_eq!(a * x + b * y, g, "for {} and {}", a, b);
+    }
+    assert_eq!(extended_gcd(240, 46).0, 2);
+    assert_eq!(mod_inverse(3, 7), Some(5));
+    assert_eq!(mod_inverse(-3, 7), Some(2));
+    assert_eq!(mod_inverse(4, 6), None);
+}
+
+#[test]
+fn test_solve_crt() {
+    assert_eq!(
+        solve_crt([(2, 3), (3, 5), (2, 7)]),
+        Ok(Congruence::new(23, 105))
+    );
+    // Moduli with common factors
+    assert_eq!(solve_crt([(2, 4), (4, 6)]), Ok(Congruence::new(10, 12)));
+    assert_eq!(
+        solve_crt([(1, 4), (2, 6)]),
+        Err(CrtError::NoSolution(
+            Congruence::new(1, 4),
+            Congruence::new(2, 6)
+        ))
+    );
+    assert_eq!(solve_crt([]), Ok(Congruence::new(0, 1)));
+    assert_eq!(solve_crt([(1, 0)]), Err(CrtError::BadModulus(0)));
+}
+
+#[test]
+fn test_mul_mod() {
+    assert_eq!(mul_mod(7, 8, 5), 1);
+    assert_eq!(mul_mod(-7, 8, 5), 4);
+    let m = i128::MAX - 2;
+    assert_eq!(mul_mod(m - 1, m - 1, m), 1);
+    assert_eq!(mul_mod(1 << 100, 1 << 100, (1 << 101) - 1), 1 << 99);
+}
+
+#[test]
+fn test_overflow() {
+    let big = (1 << 100) + 1;
+    let solved = solve_crt([(5, big), (7, 1 << 20)]).expect("Fits in an i128");
+    assert_eq!(solved.residue.rem_euclid(big), 5);
+    assert_eq!(solved.residue.rem_euclid(1 << 20), 7);
+    assert_eq!(solved.modulus, big << 20);
+    assert_eq!(
+        solve_crt([(1, big), (2, big + 2), (3, big + 4)]),
+        Err(CrtError::Overflow)
+    );
+}
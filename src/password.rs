@@ -0,0 +1,226 @@
+//! Day 2's password lines, like `1-3 a: abcde`, and a parser for them that doesn't
+//! allocate
+
+/// One line of the password database: a policy's numbers and letter, and the password
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Password<'a> {
+    pub lower: usize,
+    pub upper: usize,
+    /// What must appear in the password. This is usually a single letter, but it's kept
+    /// as text so it can also be a character made of several code points, like "é"
+    /// written as "e" followed by a combining accent.
+    pub letter: &'a str,
+    pub password: &'a str,
+}
+
+/// The parts of a password line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Lower,
+    Upper,
+    Letter,
+    Password,
+}
+
+impl std::fmt::Display for Field {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Field::Lower => "lower bound",
+            Field::Upper => "upper bound",
+            Field::Letter => "letter",
+            Field::Password => "password",
+        };
+        f.write_str(name)
+    }
+}
+
+/// What is wrong with a password line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// The line ended before the field
+    Missing(Field),
+    /// Something other than the field is where the field should be
+    Invalid(Field),
+    /// A separator is missing
+    Expected(char),
+    /// There is more after the password
+    TrailingText,
+}
+
+/// Why a password line could not be parsed, and where on the line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseError {
+    /// The character column the problem starts at, starting from 1
+    pub column: usize,
+    pub kind: ParseErrorKind,
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "column {}: ", self.column)?;
+        match self.kind {
+            ParseErrorKind::Missing(field) => write!(f, "missing {}", field),
+            ParseErrorKind::Invalid(field) => write!(f, "invalid {}", field),
+            ParseErrorKind::Expected(c) => write!(f, "expected `{}`", c),
+            ParseErrorKind::TrailingText => write!(f, "unexpected text after the password"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Walks along a line, handing out slices of it
+struct Cursor<'a> {
+    line: &'a str,
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn error(&self, kind: ParseErrorKind) -> ParseError {
+        ParseError {
+            column: self.line[..self.pos].chars().count() + 1,
+            kind,
+        }
+    }
+
+    fn at_end(&self) -> bool {
+        self.pos == self.line.len()
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.take_while(char::is_whitespace);
+    }
+
+    fn take_while(&mut self, pred: impl Fn(char) -> bool) -> &'a str {
+        let line = self.line;
+        let rest = &line[self.pos..];
+        let len = rest.find(|c| !pred(c)).unwrap_or(rest.len());
+        self.pos += len;
+        &rest[..len]
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), ParseError> {
+        self.skip_whitespace();
+        if self.line[self.pos..].starts_with(c) {
+            self.pos += c.len_utf8();
+            Ok(())
+        } else {
+            Err(self.error(ParseErrorKind::Expected(c)))
+        }
+    }
+
+    /// Take the next run of characters matching `pred`, which must not be empty
+    fn field(&mut self, field: Field, pred: impl Fn(char) -> bool) -> Result<&'a str, ParseError> {
+        self.skip_whitespace();
+        let start = self.pos;
+        let text = self.take_while(pred);
+        if !text.is_empty() {
+            return Ok(text);
+        }
+        self.pos = start;
+        Err(self.error(if self.at_end() {
+            ParseErrorKind::Missing(field)
+        } else {
+            ParseErrorKind::Invalid(field)
+        }))
+    }
+
+    fn number(&mut self, field: Field) -> Result<usize, ParseError> {
+        let digits = self.field(field, |c| c.is_ascii_digit())?;
+        digits.parse().map_err(|_| {
+            // Point at the start of the number, which is too big
+            self.pos -= digits.len();
+            self.error(ParseErrorKind::Invalid(field))
+        })
+    }
+}
+
+/// Parse a line like `1-3 a: abcde`, allowing any amount of whitespace between the
+/// parts, without allocating
+pub fn parse_password(line: &str) -> Result<Password<'_>, ParseError> {
+    let mut cursor = Cursor { line, pos: 0 };
+
+    // The range, like `1-3`
+    let lower = cursor.number(Field::Lower)?;
+    cursor.expect('-')?;
+    let upper = cursor.number(Field::Upper)?;
+
+    // The letter that must exist in the password, followed by a colon
+    let letter = cursor.field(Field::Letter, |c| c != ':' && !c.is_whitespace())?;
+    cursor.expect(':')?;
+
+    // The password itself, which is the last thing on the line
+    let password = cursor.field(Field::Password, |c| !c.is_whitespace())?;
+    cursor.skip_whitespace();
+    if !cursor.at_end() {
+        return Err(cursor.error(ParseErrorKind::TrailingText));
+    }
+
+    Ok(Password {
+        lower,
+        upper,
+        letter,
+        password,
+    })
+}
+
+#[test]
+fn test_parse_password() {
+    let expected = Password {
+        lower: 1,
+        upper: 3,
+        letter: "a",
+        password: "abcde",
+    };
+    assert_eq!(parse_password("1-3 a: abcde"), Ok(expected.clone()));
+    assert_eq!(
+        parse_password("  1 -  3\ta :abcde \r"),
+        Ok(expected.clone())
+    );
+    assert_eq!(parse_password("1-3 a:abcde"), Ok(expected));
+}
+
+#[test]
+fn test_parse_password_errors() {
+    let error = |column, kind| Err(ParseError { column, kind });
+    assert_eq!(
+        parse_password(""),
+        error(1, ParseErrorKind::Missing(Field::Lower))
+    );
+    assert_eq!(
+        parse_password("x-3 a: abc"),
+        error(1, ParseErrorKind::Invalid(Field::Lower))
+    );
+    assert_eq!(
+        parse_password("1 3 a: abc"),
+        error(3, ParseErrorKind::Expected('-'))
+    );
+    assert_eq!(
+        parse_password("1-"),
+        error(3, ParseErrorKind::Missing(Field::Upper))
+    );
+    assert_eq!(
+        parse_password("1-99999999999999999999999 a: abc"),
+        error(3, ParseErrorKind::Invalid(Field::Upper))
+    );
+    assert_eq!(
+        parse_password("1-3 : abc"),
+        error(5, ParseErrorKind::Invalid(Field::Letter))
+    );
+    assert_eq!(
+        parse_password("1-3 a abc"),
+        error(7, ParseErrorKind::Expected(':'))
+    );
+    assert_eq!(
+        parse_password("1-3 é:  "),
+        error(9, ParseErrorKind::Missing(Field::Password))
+    );
+    assert_eq!(
+        parse_password("1-3 a: abc def"),
+        error(12, ParseErrorKind::TrailingText)
+    );
+    assert_eq!(
+        parse_password("1-3 a abc").unwrap_err().to_string(),
+        "column 7: expected `:`"
+    );
+}
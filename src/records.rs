@@ -0,0 +1,149 @@
+//! Inputs made of records separated by blank lines, like day 6's groups of answers
+
+use std::io::BufRead;
+
+/// A run of non-blank lines from the input
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record<L> {
+    /// The line number the record starts on, starting from 1
+    pub line: usize,
+    /// The lines in the record, without line endings or trailing whitespace
+    pub lines: Vec<L>,
+}
+
+/// Add the line at index `idx` to the record being built, if it isn't blank. Returns
+/// false once a blank line has ended the record.
+fn add_line<L>(record: &mut Option<Record<L>>, idx: usize, line: L, blank: bool) -> bool {
+    match (record.as_mut(), blank) {
+        // A blank line after a record ends it
+        (Some(_), true) => return false,
+        // Skip the blank lines before a record
+        (None, true) => {}
+        (Some(record), false) => record.lines.push(line),
+        (None, false) => {
+            *record = Some(Record {
+                line: idx + 1,
+                lines: vec![line],
+            })
+        }
+    }
+    true
+}
+
+/// Iterates over the records in a string. Made by [`records`].
+#[derive(Debug, Clone)]
+pub struct Records<'a> {
+    lines: std::iter::Enumerate<std::str::Lines<'a>>,
+}
+
+impl<'a> Iterator for Records<'a> {
+    type Item = Record<&'a str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut record: Option<Record<&'a str>> = None;
+        for (idx, line) in self.lines.by_ref() {
+            let line = line.trim_end();
+            if !add_line(&mut record, idx, line, line.is_empty()) {
+                break;
+            }
+        }
+        record
+    }
+}
+
+/// Split `input` into records separated by blank lines.
+///
+/// Lines holding nothing but whitespace count as blank, any number of blank lines can
+/// separate two records, and blank lines at the start and end are ignored. Lines can end
+/// in `\n` or `\r\n`.
+pub fn records(input: &str) -> Records<'_> {
+    Records {
+        lines: input.lines().enumerate(),
+    }
+}
+
+/// Iterates over the records read from a reader. Made by [`read_records`].
+#[derive(Debug)]
+pub struct ReadRecords<R> {
+    lines: std::iter::Enumerate<std::io::Lines<R>>,
+}
+
+impl<R: BufRead> Iterator for ReadRecords<R> {
+    type Item = std::io::Result<Record<String>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut record: Option<Record<String>> = None;
+        for (idx, line) in self.lines.by_ref() {
+            let mut line = match line {
+                Ok(line) => line,
+                Err(e) => return Some(Err(e)),
+            };
+            line.truncate(line.trim_end().len());
+            let blank = line.is_empty();
+            if !add_line(&mut record, idx, line, blank) {
+                break;
+            }
+        }
+        record.map(Ok)
+    }
+}
+
+/// Read records separated by blank lines, in the same way as [`records`]
+pub fn read_records<R: BufRead>(reader: R) -> ReadRecords<R> {
+    ReadRecords {
+        lines: reader.lines().enumerate(),
+    }
+}
+
+#[test]
+fn test_records() {
+    let input = "a\nb\n\nc\n";
+    let got: Vec<Record<&str>> = records(input).collect();
+    assert_eq!(
+        got,
+        vec![
+            Record {
+                line: 1,
+                lines: vec!["a", "b"]
+            },
+            Record {
+                line: 4,
+                lines: vec!["c"]
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_records_messy_blank_lines() {
+    // Windows line endings, whitespace on the blank lines, several blank lines in a row,
+    // and blank lines at both ends
+    let input = "\r\n  \r\nab \r\ncd\r\n \t\r\n\r\n\r\nef\r\n\r\n\r\n";
+    let got: Vec<(usize, Vec<&str>)> = records(input).map(|r| (r.line, r.lines)).collect();
+    assert_eq!(got, vec![(3, vec!["ab", "cd"]), (8, vec!["ef"])]);
+
+    assert_eq!(records("").count(), 0);
+    assert_eq!(records("\n \n\r\n").count(), 0);
+}
+
+#[test]
+fn test_read_records() {
+    let input = "\r\n  \r\nab \r\ncd\r\n \t\r\n\r\n\r\nef\r\n\r\n\r\n";
+    let got: Vec<Record<String>> = read_records(input.as_bytes())
+        .collect::<std::io::Result<_>>()
+        .expect("Could not read records");
+    let expected: Vec<Record<String>> = records(input)
+        .map(|r| Record {
+            line: r.line,
+            lines: r.lines.iter().map(|l| l.to_string()).collect(),
+        })
+        .collect();
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn test_read_records_error() {
+    // Invalid UTF-8 can't be read as a line
+    let mut got = read_records(&b"ab\n\xff\n"[..]);
+    assert!(matches!(got.next(), Some(Err(_))));
+}
@@ -0,0 +1,158 @@
+//! Points and moves on a flat grid, like the ship sailing around on day 12
+
+use std::ops::{Add, AddAssign, Mul, Neg, Sub};
+
+/// A point or a move on a grid, with `x` going east and `y` going north
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Vec2 {
+    pub x: i64,
+    pub y: i64,
+}
+
+impl Vec2 {
+    pub const ZERO: Vec2 = Vec2 { x: 0, y: 0 };
+
+    pub const fn new(x: i64, y: i64) -> Self {
+        Vec2 { x, y }
+    }
+
+    /// How far it is from the origin, moving only along the grid
+    pub fn manhattan(self) -> i64 {
+        self.x.abs() + self.y.abs()
+    }
+
+    /// How far apart two points are, moving only along the grid
+    pub fn manhattan_to(self, other: Vec2) -> i64 {
+        (other - self).manhattan()
+    }
+
+    /// Turn anticlockwise about the origin by 90° as many times as given. Negative turns
+    /// go clockwise.
+    pub fn rotate_left(self, quarter_turns: i32) -> Vec2 {
+        match quarter_turns.rem_euclid(4) {
+            0 => self,
+            1 => Vec2::new(-self.y, self.x),
+            2 => -self,
+            _ => Vec2::new(self.y, -self.x),
+        }
+    }
+
+    /// Turn clockwise about the origin by 90° as many times as given. Negative turns go
+    /// anticlockwise.
+    pub fn rotate_right(self, quarter_turns: i32) -> Vec2 {
+        self.rotate_left(-quarter_turns)
+    }
+}
+
+impl Add for Vec2 {
+    type Output = Vec2;
+
+    fn add(self, other: Vec2) -> Vec2 {
+        Vec2::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl AddAssign for Vec2 {
+    fn add_assign(&mut self, other: Vec2) {
+        *self = *self + other;
+    }
+}
+
+impl Sub for Vec2 {
+    type Output = Vec2;
+
+    fn sub(self, other: Vec2) -> Vec2 {
+        Vec2::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+impl Neg for Vec2 {
+    type Output = Vec2;
+
+    fn neg(self) -> Vec2 {
+        Vec2::new(-self.x, -self.y)
+    }
+}
+
+impl Mul<i64> for Vec2 {
+    type Output = Vec2;
+
+    fn mul(self, times: i64) -> Vec2 {
+        Vec2::new(self.x * times, self.y * times)
+    }
+}
+
+/// One of the four points of the compass
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Direction {
+    /// All the directions, going clockwise from north
+    pub const ALL: [Direction; 4] = [
+        Direction::North,
+        Direction::East,
+        Direction::South,
+        Direction::West,
+    ];
+
+    /// A single step in this direction
+    pub fn step(self) -> Vec2 {
+        match self {
+            Direction::North => Vec2::new(0, 1),
+            Direction::East => Vec2::new(1, 0),
+            Direction::South => Vec2::new(0, -1),
+            Direction::West => Vec2::new(-1, 0),
+        }
+    }
+
+    /// Turn clockwise by 90° as many times as given. Negative turns go anticlockwise.
+    pub fn turn_right(self, quarter_turns: i32) -> Direction {
+        let idx = Direction::ALL
+            .iter()
+            .position(|&d| d == self)
+            .expect("Every direction is in ALL") as i32;
+        Direction::ALL[(idx + quarter_turns).rem_euclid(4) as usize]
+    }
+
+    /// Turn anticlockwise by 90° as many times as given. Negative turns go clockwise.
+    pub fn turn_left(self, quarter_turns: i32) -> Direction {
+        self.turn_right(-quarter_turns)
+    }
+}
+
+#[test]
+fn test_rotate() {
+    let v = Vec2::new(10, 4);
+    assert_eq!(v.rotate_right(1), Vec2::new(4, -10));
+    assert_eq!(v.rotate_left(1), Vec2::new(-4, 10));
+    assert_eq!(v.rotate_left(2), -v);
+    assert_eq!(v.rotate_left(-1), v.rotate_right(1));
+    assert_eq!(v.rotate_right(4), v);
+    assert_eq!(v.rotate_right(7), v.rotate_left(1));
+}
+
+#[test]
+fn test_turn() {
+    assert_eq!(Direction::East.turn_right(1), Direction::South);
+    assert_eq!(Direction::East.turn_left(1), Direction::North);
+    assert_eq!(Direction::North.turn_left(3), Direction::East);
+    assert_eq!(Direction::West.turn_right(-6), Direction::East);
+    for d in Direction::ALL {
+        assert_eq!(d.step().rotate_right(1), d.turn_right(1).step());
+    }
+}
+
+#[test]
+fn test_arithmetic() {
+    let mut v = Vec2::new(17, -8);
+    assert_eq!(v.manhattan(), 25);
+    assert_eq!(v.manhattan_to(Vec2::new(20, -10)), 5);
+    v += Direction::North.step() * 3;
+    assert_eq!(v, Vec2::new(17, -5));
+    assert_eq!(v - v, Vec2::ZERO);
+}